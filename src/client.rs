@@ -0,0 +1,308 @@
+//! The module that contains the reusable HTTP client.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::os::raw::c_long;
+use std::sync::Arc;
+use std::time::Duration;
+
+use Method;
+
+use cookie::CookieJar;
+use curl::Error as CurlError;
+use curl::easy::Easy;
+use curl_sys;
+use encoding::Encoding;
+use futures::Future;
+use futures::future::{self, Either};
+use request::{Request, ResponseFuture};
+use response::Response;
+use tokio_core::reactor::Handle;
+use tokio_curl::Session;
+use url::Url;
+
+type AuthFn = dyn Fn() -> String + Send + Sync;
+type InterceptFn = dyn Fn(Request) -> Request + Send + Sync;
+type ObserveFn = dyn Fn(&Response) + Send + Sync;
+
+/// Represents a reusable HTTP client.
+///
+/// A `Client` stores default settings which are applied to every request
+/// built through it, and a `Session` which all of these requests are sent
+/// through. Settings made on the request itself take precedence over
+/// the client defaults.
+///
+/// All requests sent through the session share its connection cache, so
+/// connections to the same host are reused, see
+/// [`Response::reused_connection`](struct.Response.html#method.reused_connection).
+///
+/// ```rust,no_run
+/// # extern crate tokio_core;
+/// # extern crate tokio_request;
+/// # extern crate url;
+/// use tokio_core::reactor::Core;
+/// use tokio_request::Client;
+/// use url::Url;
+///
+/// # fn main() {
+/// let mut evloop = Core::new().unwrap();
+/// let client = Client::new(evloop.handle())
+///                 .default_header("User-Agent", "tokio-request");
+/// let request = client.get(&Url::parse("https://httpbin.org/get").unwrap())
+///                 .param("Hello", "This is Rust");
+/// let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    accept_encoding: Option<Vec<Encoding>>,
+    auth_provider: Option<(Arc<AuthFn>, bool)>,
+    cookie_jar: Option<CookieJar>,
+    handle: Handle,
+    headers: Vec<(String, String)>,
+    interceptors: Vec<Arc<InterceptFn>>,
+    max_connection_age: Option<Duration>,
+    observers: Vec<Arc<ObserveFn>>,
+    session: Session,
+    timeout: Option<Duration>
+}
+
+impl Client {
+    /// Creates a new `Client` with a new `Session` on the specified event loop.
+    pub fn new(h: Handle) -> Self {
+        let session = Session::new(h.clone());
+        Client::with_session(h, session)
+    }
+
+    /// Creates a new `Client` sending all requests through the given `Session`.
+    ///
+    /// The `Session` must run on the event loop of the given `Handle`.
+    pub fn with_session(h: Handle, session: Session) -> Self {
+        Client {
+            accept_encoding: None,
+            auth_provider: None,
+            cookie_jar: None,
+            handle: h,
+            headers: Vec::new(),
+            interceptors: Vec::new(),
+            max_connection_age: None,
+            observers: Vec::new(),
+            session,
+            timeout: None
+        }
+    }
+
+    /// Sets the encodings the server may use to compress the responses to every
+    /// request built by this client.
+    ///
+    /// See [`Request::accept_encoding`](struct.Request.html#method.accept_encoding)
+    /// for more information. Calling it or [`Request::auto_decompress`](struct.Request.html#method.auto_decompress)
+    /// on a request overrides the default for that request.
+    pub fn accept_encoding(mut self, encodings: &[Encoding]) -> Self {
+        self.accept_encoding = Some(encodings.to_vec());
+        self
+    }
+
+    /// Authenticates every request sent through this client with a bearer token
+    /// supplied by the given function.
+    ///
+    /// The function is called right before each request is sent, i.e. when the
+    /// future returned by [`send`](#method.send) is first polled, so it can hand
+    /// out a cached token and fetch a new one once it expired. This
+    /// replaces any `Authorization` header of the request. If `retry_unauthorized`
+    /// is set, a request answered with status 401 is sent once more with a
+    /// token from another call, unless its body is streamed from a reader.
+    pub fn auth_provider<F>(mut self, f: F, retry_unauthorized: bool) -> Self
+            where F: Fn() -> String + Send + Sync + 'static {
+        self.auth_provider = Some((Arc::new(f), retry_unauthorized));
+        self
+    }
+
+    /// Uses the given `CookieJar` for every request built by this client,
+    /// so that cookies set by the server are sent along with subsequent requests.
+    pub fn cookie_jar(mut self, jar: CookieJar) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Adds an HTTP header that is set on every request built by this client.
+    ///
+    /// This replaces a default header with the same name set before. Setting a
+    /// header with the same name through [`Request::header`](struct.Request.html#method.header)
+    /// overrides the default for that request. Names are matched case-insensitively.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|(key, _)| !key.trim().eq_ignore_ascii_case(name.trim()));
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Sets the timeout of every request built by this client.
+    ///
+    /// See [`Request::timeout`](struct.Request.html#method.timeout) for
+    /// more information.
+    pub fn default_timeout(mut self, duration: Duration) -> Self {
+        self.timeout = Some(duration);
+        self
+    }
+
+    /// Closes connections which have been open for longer than the given
+    /// duration instead of reusing them.
+    ///
+    /// This avoids reusing connections the server is about to drop, e.g. when
+    /// it closes connections after a fixed lifetime. The age is counted from
+    /// when the connection was established and is rounded up to whole seconds.
+    /// Connections are never closed because of their age by default.
+    ///
+    /// The number of idle connections kept in the cache cannot be limited,
+    /// since cURL only takes that limit from the multi handle the session
+    /// keeps to itself. cURL closes surplus idle connections on its own
+    /// based on the number of running transfers.
+    pub fn max_connection_age(mut self, age: Duration) -> Self {
+        self.max_connection_age = Some(age);
+        self
+    }
+
+    /// Registers a function that may modify every request sent through this
+    /// client right before it is sent, e.g. to add a freshly refreshed token.
+    ///
+    /// Interceptors run in the order they were registered, each receiving the
+    /// request returned by the previous one. They also apply to requests that
+    /// were not built through this client.
+    pub fn with_interceptor<F>(mut self, f: F) -> Self
+            where F: Fn(Request) -> Request + Send + Sync + 'static {
+        self.interceptors.push(Arc::new(f));
+        self
+    }
+
+    /// Registers a function that is called with every response received through
+    /// this client, e.g. for logging.
+    ///
+    /// Observers run in the order they were registered, after all
+    /// [retries](struct.Request.html#method.retry) of a request. They are not
+    /// called for requests that failed without a response.
+    pub fn with_observer<F>(mut self, f: F) -> Self
+            where F: Fn(&Response) + Send + Sync + 'static {
+        self.observers.push(Arc::new(f));
+        self
+    }
+
+    /// Builds a GET-Request to the specified URL.
+    pub fn get(&self, url: &Url) -> Request {
+        self.request(url, Method::Get)
+    }
+
+    /// Builds a DELETE-Request to the specified URL.
+    pub fn delete(&self, url: &Url) -> Request {
+        self.request(url, Method::Delete)
+    }
+
+    /// Builds a PATCH-Request to the specified URL.
+    pub fn patch(&self, url: &Url) -> Request {
+        self.request(url, Method::Patch)
+    }
+
+    /// Builds a POST-Request to the specified URL.
+    pub fn post(&self, url: &Url) -> Request {
+        self.request(url, Method::Post)
+    }
+
+    /// Builds a PUT-Request to the specified URL.
+    pub fn put(&self, url: &Url) -> Request {
+        self.request(url, Method::Put)
+    }
+
+    /// Builds a request with the specified method to the specified URL
+    /// pre-populated with the client defaults.
+    pub fn request(&self, url: &Url, method: Method) -> Request {
+        let mut request = Request::new(url, method).headers(self.headers.clone());
+        if let Some(ref encodings) = self.accept_encoding {
+            request = request.accept_encoding(encodings);
+        }
+        if let Some(ref jar) = self.cookie_jar {
+            request = request.cookie_jar(jar.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(age) = self.max_connection_age {
+            request = request.configure(move |easy| set_max_lifetime(easy, age));
+        }
+        request
+    }
+
+    /// Sends the given request through the session of this client.
+    ///
+    /// See [`Request::send`](struct.Request.html#method.send) for more information.
+    pub fn send(&self, request: Request) -> ResponseFuture {
+        let request = self.interceptors.iter().fold(request, |request, intercept| intercept(request));
+        let response: ResponseFuture = match self.auth_provider {
+            Some((ref provider, retry_unauthorized)) => {
+                let retry = if retry_unauthorized && request.is_repeatable() { Some(request.clone()) } else { None };
+                // Fetch the token once the request is actually sent, not when the future is created
+                let response = {
+                    let provider = provider.clone();
+                    let session = self.session.clone();
+                    let handle = self.handle.clone();
+                    Box::new(future::lazy(move || request.bearer_auth(&provider()).send_retrying(&session, &handle)))
+                };
+                match retry {
+                    Some(retry) => {
+                        let provider = provider.clone();
+                        let session = self.session.clone();
+                        let handle = self.handle.clone();
+                        Box::new(response.and_then(move |response| if response.status_code() == 401 {
+                            Either::A(retry.bearer_auth(&provider()).send_retrying(&session, &handle))
+                        } else {
+                            Either::B(future::ok(response))
+                        }))
+                    },
+                    None => response
+                }
+            },
+            None => request.send_retrying(&self.session, &self.handle)
+        };
+        if self.observers.is_empty() {
+            return response;
+        }
+
+        let observers = self.observers.clone();
+        Box::new(response.map(move |response| {
+            for observe in &observers {
+                observe(&response);
+            }
+            response
+        }))
+    }
+
+    /// Gets the `Session` requests are sent through.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+impl Debug for Client {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.debug_struct(stringify!(Client))
+            .field("accept_encoding", &self.accept_encoding)
+            .field("auth_provider", &self.auth_provider.is_some())
+            .field("cookie_jar", &self.cookie_jar)
+            .field("headers", &self.headers)
+            .field("interceptors", &self.interceptors.len())
+            .field("max_connection_age", &self.max_connection_age)
+            .field("observers", &self.observers.len())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Sets `CURLOPT_MAXLIFETIME_CONN`, which the `curl` crate doesn't expose.
+fn set_max_lifetime(easy: &mut Easy, age: Duration) -> Result<(), CurlError> {
+    const CURLOPT_MAXLIFETIME_CONN: curl_sys::CURLoption = curl_sys::CURLOPTTYPE_LONG + 314;
+
+    // Zero would disable the limit altogether
+    let secs = (age.as_secs() + u64::from(age.subsec_nanos() > 0)).max(1);
+    let secs = secs.min(c_long::MAX as u64) as c_long;
+    match unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_MAXLIFETIME_CONN, secs) } {
+        curl_sys::CURLE_OK => Ok(()),
+        code => Err(CurlError::new(code))
+    }
+}
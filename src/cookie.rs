@@ -0,0 +1,264 @@
+//! The module that contains the cookie handling code.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use httpdate::parse_http_date;
+use url::{Host, Url};
+
+/// Represents a cookie as sent by the server in a `Set-Cookie` header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cookie {
+    /// The name of the cookie.
+    pub name: String,
+    /// The value of the cookie.
+    pub value: String,
+    /// The domain the cookie is valid for, if specified.
+    pub domain: Option<String>,
+    /// The path the cookie is valid for, if specified.
+    pub path: Option<String>,
+    /// The point in time the cookie expires at, if specified.
+    ///
+    /// This is computed from the `Max-Age` attribute if present. A `Max-Age`
+    /// too far in the future to be represented yields `None`.
+    pub expires: Option<SystemTime>,
+    /// Whether the cookie must only be sent over secure connections.
+    pub secure: bool,
+    /// Whether the cookie is inaccessible to scripts.
+    pub http_only: bool
+}
+
+impl Cookie {
+    /// Attempts to parse a cookie from the value of a `Set-Cookie` header.
+    ///
+    /// Unknown or malformed attributes are ignored. Returns `None` if the
+    /// header does not start with a `name=value` pair.
+    pub fn parse(header: &str) -> Option<Cookie> {
+        let mut parts = header.split(';');
+        let (name, value) = match parts.next().map(|pair| pair.splitn(2, '=')) {
+            Some(mut pair) => match (pair.next(), pair.next()) {
+                (Some(name), Some(value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+                _ => return None
+            },
+            None => return None
+        };
+
+        let mut cookie = Cookie {
+            name: name.to_owned(),
+            value: value.trim_matches('"').to_owned(),
+            domain: None,
+            path: None,
+            expires: None,
+            secure: false,
+            http_only: false
+        };
+        let mut max_age = None;
+        for attribute in parts {
+            let mut attribute = attribute.splitn(2, '=');
+            let key = attribute.next().unwrap_or("").trim().to_ascii_lowercase();
+            let value = attribute.next().map(|v| v.trim()).unwrap_or("");
+            match key.as_ref() {
+                "domain" if !value.is_empty() => {
+                    cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase());
+                },
+                "expires" => cookie.expires = parse_expires(value),
+                "httponly" => cookie.http_only = true,
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "path" if value.starts_with('/') => cookie.path = Some(value.to_owned()),
+                "secure" => cookie.secure = true,
+                _ => {}
+            }
+        }
+        if let Some(secs) = max_age {
+            // Max-Age takes precedence over Expires, and a Max-Age too large
+            // to represent makes the cookie last for the session
+            cookie.expires = if secs > 0 {
+                SystemTime::now().checked_add(Duration::from_secs(secs as u64))
+            } else {
+                Some(SystemTime::UNIX_EPOCH)
+            };
+        }
+
+        Some(cookie)
+    }
+
+    /// Checks whether the cookie has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= SystemTime::now())
+    }
+}
+
+/// An in-memory store for cookies, which sends them back to the servers
+/// they came from.
+///
+/// Cloning a `CookieJar` is cheap and yields a handle to the same store, so
+/// a jar can be shared between multiple requests. See
+/// [`Request::cookie_jar`](struct.Request.html#method.cookie_jar) and
+/// [`Client::cookie_jar`](struct.Client.html#method.cookie_jar).
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<StoredCookie>>>
+}
+
+impl CookieJar {
+    /// Creates a new, empty `CookieJar`.
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Gets all cookies that have not expired yet.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let mut cookies = self.cookies.lock().unwrap();
+        cookies.retain(|stored| !stored.cookie.is_expired());
+        cookies.iter().map(|stored| stored.cookie.clone()).collect()
+    }
+
+    /// Builds the value of the `Cookie` header to send along with a request
+    /// to the given URL.
+    ///
+    /// Returns `None` if there are no cookies to send.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return None
+        };
+        let is_secure = url.scheme() == "https";
+
+        let mut cookies = self.cookies.lock().unwrap();
+        cookies.retain(|stored| !stored.cookie.is_expired());
+        let header = cookies.iter()
+            .filter(|stored| stored.matches(&host, url.path(), is_secure))
+            .map(|stored| format!("{}={}", stored.cookie.name, stored.cookie.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        if header.is_empty() {
+            None
+        } else {
+            Some(header)
+        }
+    }
+
+    /// Stores the given cookie received from the given URL.
+    ///
+    /// Cookies for a domain the URL does not belong to are rejected.
+    /// Expired cookies remove previously stored cookies of the same name.
+    pub fn store(&self, url: &Url, cookie: Cookie) {
+        let host = match url.host_str() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return
+        };
+        let (domain, host_only) = match cookie.domain {
+            Some(ref domain) if is_ip(url) && *domain != host => return,
+            Some(ref domain) if !domain_matches(&host, domain) => return,
+            Some(ref domain) => (domain.clone(), false),
+            None => (host, true)
+        };
+        let path = cookie.path.clone().unwrap_or_else(|| default_path(url));
+
+        let mut cookies = self.cookies.lock().unwrap();
+        cookies.retain(|stored| {
+            !(stored.cookie.name == cookie.name && stored.domain == domain && stored.path == path)
+        });
+        if !cookie.is_expired() {
+            cookies.push(StoredCookie {
+                cookie,
+                domain,
+                host_only,
+                path
+            });
+        }
+    }
+}
+
+/// A cookie together with the resolved scope it is valid for.
+#[derive(Clone, Debug)]
+struct StoredCookie {
+    cookie: Cookie,
+    domain: String,
+    host_only: bool,
+    path: String
+}
+
+impl StoredCookie {
+    fn matches(&self, host: &str, path: &str, is_secure: bool) -> bool {
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(host, &self.domain)
+        };
+        domain_ok && path_matches(path, &self.path) && (is_secure || !self.cookie.secure)
+    }
+}
+
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(idx) => path[..idx].to_owned()
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+fn is_ip(url: &Url) -> bool {
+    matches!(url.host(), Some(Host::Ipv4(_)) | Some(Host::Ipv6(_)))
+}
+
+fn parse_expires(value: &str) -> Option<SystemTime> {
+    // Cookies often use dashes in their dates (Wed, 21-Oct-2015 07:28:00 GMT)
+    parse_http_date(value)
+        .or_else(|_| parse_http_date(&value.replace('-', " ")))
+        .ok()
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path ||
+        (request_path.starts_with(cookie_path) &&
+            (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn domain_and_path_matching() {
+        let jar = CookieJar::new();
+        jar.store(&url("https://www.example.com/account/login"), Cookie::parse("session=abc").unwrap());
+        jar.store(&url("https://www.example.com/"), Cookie::parse("lang=en; Domain=.example.com; Path=/").unwrap());
+        jar.store(&url("https://www.example.com/"), Cookie::parse("evil=1; Domain=other.com").unwrap());
+
+        assert_eq!(jar.cookie_header(&url("https://www.example.com/account/settings")),
+                   Some("session=abc; lang=en".to_owned()));
+        assert_eq!(jar.cookie_header(&url("https://api.example.com/accounts")),
+                   Some("lang=en".to_owned()));
+        assert_eq!(jar.cookie_header(&url("https://other.com/")), None);
+    }
+
+    #[test]
+    fn secure_and_expired() {
+        let jar = CookieJar::new();
+        jar.store(&url("https://example.com/"), Cookie::parse("token=1; Secure; HttpOnly").unwrap());
+        jar.store(&url("https://example.com/"), Cookie::parse("old=1; Expires=Wed, 21-Oct-2015 07:28:00 GMT").unwrap());
+
+        assert_eq!(jar.cookie_header(&url("http://example.com/")), None);
+        assert_eq!(jar.cookie_header(&url("https://example.com/")), Some("token=1".to_owned()));
+
+        jar.store(&url("https://example.com/"), Cookie::parse("token=; Max-Age=0").unwrap());
+        assert!(jar.cookies().is_empty());
+    }
+
+    #[test]
+    fn max_age_overflow() {
+        let cookie = Cookie::parse("a=b; Max-Age=9223372036854775807").unwrap();
+        assert_eq!(cookie.expires, None);
+        assert!(!cookie.is_expired());
+    }
+}
@@ -0,0 +1,61 @@
+//! The module that contains the types of cURL's verbose debug output.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use curl::easy::InfoType;
+
+/// Represents the kind of data passed to a debug callback.
+///
+/// See [`Request::debug_callback`](struct.Request.html#method.debug_callback)
+/// for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DebugType {
+    /// Protocol data received from the server
+    DataIn,
+    /// Protocol data sent to the server
+    DataOut,
+    /// Header data received from the server
+    HeaderIn,
+    /// Header data sent to the server
+    HeaderOut,
+    /// Raw TLS data received from the server
+    SslDataIn,
+    /// Raw TLS data sent to the server
+    SslDataOut,
+    /// Informational text from cURL
+    Text
+}
+
+impl AsRef<str> for DebugType {
+    fn as_ref(&self) -> &str {
+        match *self {
+            DebugType::DataIn => "DATA_IN",
+            DebugType::DataOut => "DATA_OUT",
+            DebugType::HeaderIn => "HEADER_IN",
+            DebugType::HeaderOut => "HEADER_OUT",
+            DebugType::SslDataIn => "SSL_DATA_IN",
+            DebugType::SslDataOut => "SSL_DATA_OUT",
+            DebugType::Text => "TEXT"
+        }
+    }
+}
+
+impl Display for DebugType {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(self.as_ref())
+    }
+}
+
+impl From<InfoType> for DebugType {
+    fn from(kind: InfoType) -> Self {
+        match kind {
+            InfoType::DataIn => DebugType::DataIn,
+            InfoType::DataOut => DebugType::DataOut,
+            InfoType::HeaderIn => DebugType::HeaderIn,
+            InfoType::HeaderOut => DebugType::HeaderOut,
+            InfoType::SslDataIn => DebugType::SslDataIn,
+            InfoType::SslDataOut => DebugType::SslDataOut,
+            _ => DebugType::Text
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! The module that contains the supported content encodings.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+/// Represents an HTTP content encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// deflate (zlib-wrapped, as mandated by RFC 7230)
+    Deflate,
+    /// gzip
+    Gzip
+}
+
+impl Encoding {
+    /// Compresses the given data using this encoding.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        // Writing to a Vec cannot fail, so the results can safely be unwrapped
+        match *self {
+            Encoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            },
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+}
+
+impl AsRef<str> for Encoding {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Encoding::Deflate => "deflate",
+            Encoding::Gzip => "gzip"
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(self.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    #[test]
+    fn round_trip() {
+        let data = "tokio-request ".repeat(100).into_bytes();
+
+        let gzipped = Encoding::Gzip.encode(&data);
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gzipped[..]).read_to_end(&mut decoded).unwrap();
+        assert!(gzipped.len() < data.len());
+        assert_eq!(decoded, data);
+
+        let deflated = Encoding::Deflate.encode(&data);
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(&deflated[..]).read_to_end(&mut decoded).unwrap();
+        assert!(deflated.len() < data.len());
+        assert_eq!(decoded, data);
+    }
+}
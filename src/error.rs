@@ -0,0 +1,178 @@
+//! The module that contains the error type of failed requests.
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::io;
+
+use curl;
+use status::StatusCode;
+use url::{ParseError, Url};
+
+/// Represents the reason a request has failed.
+#[derive(Debug)]
+pub enum RequestError {
+    /// The response body exceeded the configured maximum size.
+    ///
+    /// See [`Request::max_body_size`](struct.Request.html#method.max_body_size)
+    /// for more information.
+    BodyTooLarge,
+    /// The request was aborted through its cancel handle.
+    ///
+    /// See [`Request::send_cancelable`](struct.Request.html#method.send_cancelable)
+    /// for more information.
+    Cancelled,
+    /// cURL reported an error while configuring or performing the transfer.
+    Curl(curl::Error),
+    /// The response body could not be decoded, e.g. from JSON.
+    ///
+    /// See [`Response::json`](struct.Response.html#method.json)
+    /// for more information.
+    Decode(Box<dyn Error + Send + Sync>),
+    /// The cipher list passed to [`Request::ciphers`](struct.Request.html#method.ciphers)
+    /// or [`Request::tls13_ciphers`](struct.Request.html#method.tls13_ciphers)
+    /// is empty.
+    EmptyCipherList,
+    /// The request body could not be encoded, e.g. to JSON.
+    ///
+    /// See [`Request::json`](struct.Request.html#method.json)
+    /// for more information.
+    Encode(Box<dyn Error + Send + Sync>),
+    /// The value passed as `Host` header is not a valid host.
+    ///
+    /// See [`Request::host`](struct.Request.html#method.host)
+    /// for more information.
+    InvalidHost(String),
+    /// An I/O error occured while driving the transfer on the event loop.
+    Io(io::Error),
+    /// The transfer was slower than the configured lowspeed limit.
+    ///
+    /// See [`Request::lowspeed_limit`](struct.Request.html#method.lowspeed_limit)
+    /// for more information.
+    LowSpeed,
+    /// The server answered with a status code that doesn't represent a success.
+    ///
+    /// Contains the status code and the URL of the response. See
+    /// [`Response::error_for_status`](struct.Response.html#method.error_for_status)
+    /// for more information.
+    Status(u16, Url),
+    /// The request took longer than the configured timeout.
+    ///
+    /// See [`Request::timeout`](struct.Request.html#method.timeout)
+    /// for more information.
+    Timeout,
+    /// The server redirected more often than allowed.
+    ///
+    /// See [`Request::max_redirects`](struct.Request.html#method.max_redirects)
+    /// for more information.
+    TooManyRedirects,
+    /// The URL of the request could not be parsed.
+    UrlParse(ParseError)
+}
+
+impl Display for RequestError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match *self {
+            RequestError::BodyTooLarge => fmt.write_str("The response body exceeded the maximum size."),
+            RequestError::Cancelled => fmt.write_str("The request was cancelled."),
+            RequestError::Curl(ref err) => write!(fmt, "cURL error: {}", err),
+            RequestError::Decode(ref err) => write!(fmt, "Failed to decode the response body: {}", err),
+            RequestError::EmptyCipherList => fmt.write_str("The cipher list must not be empty."),
+            RequestError::Encode(ref err) => write!(fmt, "Failed to encode the request body: {}", err),
+            RequestError::InvalidHost(ref host) => write!(fmt, "Invalid host: {}", host),
+            RequestError::Io(ref err) => write!(fmt, "I/O error: {}", err),
+            RequestError::LowSpeed => fmt.write_str("The transfer was aborted due to too low speeds."),
+            RequestError::Status(code, ref url) => write!(fmt, "Unsuccessful status code {} from {}", code, url),
+            RequestError::Timeout => fmt.write_str("The request timed out."),
+            RequestError::TooManyRedirects => fmt.write_str("The maximum amount of redirects was exceeded."),
+            RequestError::UrlParse(ref err) => write!(fmt, "Invalid URL: {}", err)
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RequestError::Curl(ref err) => Some(err),
+            RequestError::Decode(ref err) => Some(&**err),
+            RequestError::Encode(ref err) => Some(&**err),
+            RequestError::Io(ref err) => Some(err),
+            RequestError::UrlParse(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<curl::Error> for RequestError {
+    fn from(err: curl::Error) -> Self {
+        if err.is_operation_timedout() {
+            RequestError::Timeout
+        } else if err.is_too_many_redirects() {
+            RequestError::TooManyRedirects
+        } else {
+            RequestError::Curl(err)
+        }
+    }
+}
+
+impl From<io::Error> for RequestError {
+    fn from(err: io::Error) -> Self {
+        // tokio-curl hands out cURL errors wrapped inside an io::Error
+        if err.get_ref().is_some_and(|inner| inner.is::<curl::Error>()) {
+            let inner = err.into_inner().unwrap().downcast::<curl::Error>().unwrap();
+            RequestError::from(*inner)
+        } else {
+            RequestError::Io(err)
+        }
+    }
+}
+
+impl From<ParseError> for RequestError {
+    fn from(err: ParseError) -> Self {
+        RequestError::UrlParse(err)
+    }
+}
+
+/// Represents the reason a request to an API that describes failures in the
+/// response body has failed.
+///
+/// See [`Response::json_or_error`](struct.Response.html#method.json_or_error)
+/// for more information.
+#[derive(Debug)]
+pub enum ApiError<E> {
+    /// The request itself failed or a successful response body could not be decoded.
+    Request(RequestError),
+    /// The server answered with a status code that doesn't represent a success.
+    ///
+    /// Contains the status code and the decoded error body.
+    Status(StatusCode, E),
+    /// The server answered with a status code that doesn't represent a success
+    /// and an error body that could not be decoded, e.g. an HTML error page.
+    ///
+    /// Contains the status code and the reason the body could not be decoded.
+    StatusUndecodable(StatusCode, RequestError)
+}
+
+impl<E: Debug> Display for ApiError<E> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match *self {
+            ApiError::Request(ref err) => Display::fmt(err, fmt),
+            ApiError::Status(status, ref body) => write!(fmt, "Unsuccessful status code {}: {:?}", status, body),
+            ApiError::StatusUndecodable(status, ref err) => write!(fmt, "Unsuccessful status code {}: {}", status, err)
+        }
+    }
+}
+
+impl<E: Debug> Error for ApiError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ApiError::Request(ref err) | ApiError::StatusUndecodable(_, ref err) => Some(err),
+            ApiError::Status(..) => None
+        }
+    }
+}
+
+impl<E> From<RequestError> for ApiError<E> {
+    fn from(err: RequestError) -> Self {
+        ApiError::Request(err)
+    }
+}
@@ -52,6 +52,14 @@
 //! );
 //! ```
 //!
+//! # Serialization
+//! JSON support is provided either by `serde` through the default
+//! `serde-serialization` feature or by `rustc-serialize` through the
+//! `rustc-serialization` feature. Both provide `Request::json`,
+//! `Response::json` and `Response::json_value` with the same semantics, only
+//! the traits bounding the data differ. If both features are enabled, `serde`
+//! takes precedence.
+//!
 //! # Caveats
 //! Right now the focus for this library is on interacting with REST
 //! APIs that talk JSON, so this library is buffering the entire response
@@ -63,12 +71,18 @@
 #![deny(missing_docs)]
 
 extern crate curl;
+extern crate curl_sys;
+extern crate encoding_rs;
+extern crate flate2;
 extern crate futures;
+extern crate httpdate;
 extern crate mime;
 extern crate tokio_core;
 extern crate tokio_curl;
 extern crate url;
 
+#[cfg(test)]
+extern crate openssl;
 #[cfg(feature = "rustc-serialization")]
 extern crate rustc_serialize;
 
@@ -76,16 +90,42 @@ extern crate rustc_serialize;
 extern crate serde;
 #[cfg(feature = "serde-serialization")]
 extern crate serde_json;
+#[cfg(feature = "serde-serialization")]
+extern crate serde_urlencoded;
 
+mod client;
+mod cookie;
+mod debug;
+mod encoding;
+mod error;
+mod multipart;
+mod query;
 mod request;
 mod response;
+mod retry;
+mod status;
+mod stream;
+mod tls;
 
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use url::Url;
 
+pub use self::client::*;
+pub use self::cookie::*;
+pub use self::debug::*;
+pub use self::encoding::*;
+pub use self::error::*;
+pub use self::multipart::*;
+pub use self::query::*;
 pub use self::request::*;
 pub use self::response::*;
+pub use self::retry::*;
+pub use self::status::*;
+pub use self::stream::*;
+pub use self::tls::*;
 
 /// Issue a GET-Request to the specified URL.
 pub fn get(url: &Url) -> Request {
@@ -97,6 +137,26 @@ pub fn delete(url: &Url) -> Request {
     request(url, Method::Delete)
 }
 
+/// Issue a HEAD-Request to the specified URL.
+///
+/// The response contains the status code and headers, but no body.
+pub fn head(url: &Url) -> Request {
+    request(url, Method::Head)
+}
+
+/// Issue an OPTIONS-Request to the specified URL.
+///
+/// See [`Response::allowed_methods`](struct.Response.html#method.allowed_methods)
+/// to read the methods the server supports.
+pub fn options(url: &Url) -> Request {
+    request(url, Method::Options)
+}
+
+/// Issue a PATCH-Request to the specified URL.
+pub fn patch(url: &Url) -> Request {
+    request(url, Method::Patch)
+}
+
 /// Issue a POST-Request to the specified URL.
 pub fn post(url: &Url) -> Request {
     request(url, Method::Post)
@@ -115,39 +175,151 @@ pub fn request(url: &Url, method: Method) -> Request {
 /// A submodule which allows the request builder functions to be
 /// used with string slices instead of URLs for convenience.
 pub mod str {
-    use ::{Method, Request};
+    use ::{Method, Request, RequestError};
     use url::Url;
 
     /// Issue a GET-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_get`](fn.try_get.html)
+    /// for a non-panicking version.
     pub fn get(url: &str) -> Request {
         request(url, Method::Get)
     }
 
     /// Issue a DELETE-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_delete`](fn.try_delete.html)
+    /// for a non-panicking version.
     pub fn delete(url: &str) -> Request {
         request(url, Method::Delete)
     }
 
+    /// Issue a HEAD-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_head`](fn.try_head.html)
+    /// for a non-panicking version.
+    pub fn head(url: &str) -> Request {
+        request(url, Method::Head)
+    }
+
+    /// Issue an OPTIONS-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_options`](fn.try_options.html)
+    /// for a non-panicking version.
+    pub fn options(url: &str) -> Request {
+        request(url, Method::Options)
+    }
+
+    /// Issue a PATCH-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_patch`](fn.try_patch.html)
+    /// for a non-panicking version.
+    pub fn patch(url: &str) -> Request {
+        request(url, Method::Patch)
+    }
+
     /// Issue a POST-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_post`](fn.try_post.html)
+    /// for a non-panicking version.
     pub fn post(url: &str) -> Request {
         request(url, Method::Post)
     }
 
     /// Issue a PUT-Request to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_put`](fn.try_put.html)
+    /// for a non-panicking version.
     pub fn put(url: &str) -> Request {
         request(url, Method::Put)
     }
 
     /// Issue a request with the specified method to the specified URL.
+    ///
+    /// ## Panics
+    /// Panics if the URL cannot be parsed. See [`try_request`](fn.try_request.html)
+    /// for a non-panicking version.
     pub fn request(url: &str, method: Method) -> Request {
         super::request(&Url::parse(url).unwrap(), method)
     }
+
+    /// Issue a GET-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_get(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Get)
+    }
+
+    /// Issue a DELETE-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_delete(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Delete)
+    }
+
+    /// Issue a HEAD-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_head(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Head)
+    }
+
+    /// Issue an OPTIONS-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_options(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Options)
+    }
+
+    /// Issue a PATCH-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_patch(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Patch)
+    }
+
+    /// Issue a POST-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_post(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Post)
+    }
+
+    /// Issue a PUT-Request to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_put(url: &str) -> Result<Request, RequestError> {
+        try_request(url, Method::Put)
+    }
+
+    /// Issue a request with the specified method to the specified URL.
+    ///
+    /// Returns [`RequestError::UrlParse`](../enum.RequestError.html#variant.UrlParse)
+    /// if the URL cannot be parsed.
+    pub fn try_request(url: &str, method: Method) -> Result<Request, RequestError> {
+        Ok(super::request(&Url::parse(url)?, method))
+    }
 }
 
 /// Represents an HTTP method.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub enum Method {
     /// GET
+    #[default]
     Get,
     /// POST
     Post,
@@ -165,7 +337,9 @@ pub enum Method {
     Patch,
     /// OPTIONS
     Options,
-    /// A custom HTTP header.
+    /// Any other method, e.g. the WebDAV `REPORT`.
+    ///
+    /// The method name is sent as is, along with the request body if one is set.
     Custom(String)
 }
 
@@ -186,13 +360,114 @@ impl AsRef<str> for Method {
     }
 }
 
-impl Default for Method {
-    fn default() -> Self {
-        Method::Get
+impl Display for Method {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(self.as_ref())
     }
 }
 
-impl Display for Method {
+impl FromStr for Method {
+    type Err = Infallible;
+
+    /// Parses the standard methods case-insensitively, any other method
+    /// becomes a [`Method::Custom`](enum.Method.html#variant.Custom).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_uppercase().as_ref() {
+            "CONNECT" => Method::Connect,
+            "DELETE" => Method::Delete,
+            "GET" => Method::Get,
+            "HEAD" => Method::Head,
+            "OPTIONS" => Method::Options,
+            "PATCH" => Method::Patch,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "TRACE" => Method::Trace,
+            _ => Method::Custom(s.to_owned())
+        })
+    }
+}
+
+#[cfg(feature = "serde-serialization")]
+impl serde::Serialize for Method {
+    fn serialize<S: serde::Serializer>(&self, serializer: &mut S) -> Result<(), S::Error> {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
+#[cfg(feature = "serde-serialization")]
+impl serde::Deserialize for Method {
+    fn deserialize<D: serde::Deserializer>(deserializer: &mut D) -> Result<Self, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        Ok(method.parse().unwrap())
+    }
+}
+
+/// Represents an HTTP protocol version.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HttpVersion {
+    /// HTTP/1.0
+    Http10,
+    /// HTTP/1.1
+    Http11,
+    /// HTTP/2, negotiated via TLS-ALPN or the `Upgrade` header
+    Http2,
+    /// HTTP/2 without negotiation, also over plain TCP
+    Http2PriorKnowledge
+}
+
+impl AsRef<str> for HttpVersion {
+    fn as_ref(&self) -> &str {
+        match *self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 | HttpVersion::Http2PriorKnowledge => "HTTP/2"
+        }
+    }
+}
+
+impl Display for HttpVersion {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(self.as_ref())
+    }
+}
+
+/// Represents the IP versions host names may resolve to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IpResolve {
+    /// Any IP version the system supports
+    #[default]
+    Any,
+    /// Only IPv4 addresses
+    V4,
+    /// Only IPv6 addresses
+    V6
+}
+
+/// Represents a TLS protocol version.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TlsVersion {
+    /// TLS 1.0
+    Tls10,
+    /// TLS 1.1
+    Tls11,
+    /// TLS 1.2
+    Tls12,
+    /// TLS 1.3
+    Tls13
+}
+
+impl AsRef<str> for TlsVersion {
+    fn as_ref(&self) -> &str {
+        match *self {
+            TlsVersion::Tls10 => "TLSv1.0",
+            TlsVersion::Tls11 => "TLSv1.1",
+            TlsVersion::Tls12 => "TLSv1.2",
+            TlsVersion::Tls13 => "TLSv1.3"
+        }
+    }
+}
+
+impl Display for TlsVersion {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
         fmt.write_str(self.as_ref())
     }
@@ -200,6 +475,188 @@ impl Display for Method {
 
 #[cfg(test)]
 mod tests {
+    use url::Url;
+
+    /// Answers the first connection to a local port with the given raw
+    /// HTTP response and returns the URL to connect to.
+    pub fn serve(response: &'static [u8]) -> Url {
+        respond(move |_| response.to_vec())
+    }
+
+    /// Answers the first connection to a local port with the raw request
+    /// as response body and returns the URL to connect to.
+    pub fn echo() -> Url {
+        respond(|request| {
+            let mut response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", request.len()).into_bytes();
+            response.extend_from_slice(request);
+            response
+        })
+    }
+
+    /// Answers the first connections to a local port with the given raw HTTP
+    /// responses in order and returns the URL to connect to.
+    ///
+    /// An empty response closes the connection without answering.
+    pub fn serve_sequence(responses: &'static [&'static [u8]]) -> Url {
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                handle(stream, |_| response.to_vec());
+            }
+        });
+        url
+    }
+
+    /// Answers the given number of requests on the first connection to a local
+    /// port with the given raw HTTP response, keeping the connection open in
+    /// between, and returns the URL to connect to.
+    pub fn serve_keep_alive(response: &'static [u8], requests: usize) -> Url {
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            for _ in 0..requests {
+                handle(&stream, |_| response.to_vec());
+            }
+        });
+        url
+    }
+
+    /// Accepts connections to a local port without ever answering them and
+    /// returns the URL to connect to.
+    pub fn stall() -> Url {
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let streams: Vec<_> = listener.incoming().collect();
+            drop(streams);
+        });
+        url
+    }
+
+    /// Answers the first TLS connection to a local port with the given raw
+    /// HTTP response and returns the URL to connect to.
+    ///
+    /// The server presents the certificate in `tests/certs/server.pem`, which is
+    /// issued for `localhost` by the CA in `tests/certs/ca.pem`.
+    pub fn serve_tls(response: &'static [u8]) -> Url {
+        tls_server(response, false, None)
+    }
+
+    /// Like [`serve_tls`], but refuses protocol versions newer than TLS 1.2.
+    pub fn serve_tls12(response: &'static [u8]) -> Url {
+        use openssl::ssl::SslVersion;
+
+        tls_server(response, false, Some(SslVersion::TLS1_2))
+    }
+
+    /// Like [`serve_tls`], but additionally requires the client to present a
+    /// certificate issued by the CA in `tests/certs/ca.pem`.
+    pub fn serve_mutual_tls(response: &'static [u8]) -> Url {
+        tls_server(response, true, None)
+    }
+
+    fn tls_server(response: &'static [u8], verify_client: bool, max_version: Option<::openssl::ssl::SslVersion>) -> Url {
+        use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+        use std::net::TcpListener;
+        use std::thread;
+
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate_chain_file(cert_path("server.pem")).unwrap();
+        acceptor.set_private_key_file(cert_path("server.key"), SslFiletype::PEM).unwrap();
+        if verify_client {
+            acceptor.set_ca_file(cert_path("ca.pem")).unwrap();
+            acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        }
+        acceptor.set_max_proto_version(max_version).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("https://localhost:{}/", listener.local_addr().unwrap().port())).unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            if let Ok(stream) = acceptor.accept(stream) {
+                handle(stream, |_| response.to_vec());
+            }
+        });
+        url
+    }
+
+    /// Gets the path of the given file in `tests/certs`.
+    pub fn cert_path(name: &str) -> ::std::path::PathBuf {
+        ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("certs").join(name)
+    }
+
+    fn respond<F: FnOnce(&[u8]) -> Vec<u8> + Send + 'static>(f: F) -> Url {
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle(stream, f);
+        });
+        url
+    }
+
+    fn handle<S: ::std::io::Read + ::std::io::Write, F: FnOnce(&[u8]) -> Vec<u8>>(stream: S, f: F) {
+        use std::io::{BufRead, BufReader, Read};
+
+        let mut reader = BufReader::new(stream);
+
+        // Consume the entire request before answering
+        let mut chunked = false;
+        let mut content_length = 0;
+        let mut request = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let lower = line.to_ascii_lowercase();
+            if lower.starts_with("content-length:") {
+                content_length = line[15..].trim().parse().unwrap();
+            } else if lower.starts_with("transfer-encoding:") && lower.contains("chunked") {
+                chunked = true;
+            }
+            request.extend_from_slice(line.as_bytes());
+            if line.len() <= 2 {
+                break;
+            }
+        }
+        if chunked {
+            // Passes the chunks on as they are, including the terminating one
+            loop {
+                let mut size = String::new();
+                reader.read_line(&mut size).unwrap();
+                request.extend_from_slice(size.as_bytes());
+                let len = usize::from_str_radix(size.trim().split(';').next().unwrap(), 16).unwrap();
+                let mut chunk = vec![0; len + 2];
+                reader.read_exact(&mut chunk).unwrap();
+                request.extend(chunk);
+                if len == 0 {
+                    break;
+                }
+            }
+        } else {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            request.extend(body);
+        }
+
+        reader.into_inner().write_all(&f(&request)).unwrap();
+    }
+
     macro_rules! generate_str_tests {
         ($name:ident) => {
             #[test]
@@ -260,4 +717,2148 @@ mod tests {
         generate_url_tests!(put);
         generate_url_tests!(delete);
     }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn accept_encoding() {
+        use ::Encoding;
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/gzip")
+            .accept_gzip()
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.json_value().is_ok());
+
+        let request = get("https://httpbin.org/deflate")
+            .accept_encoding(&[Encoding::Deflate])
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.json_value().is_ok());
+    }
+
+    #[test]
+    fn auto_decompress() {
+        use ::Encoding;
+        use ::str::get;
+        use curl::Version;
+        use tokio_core::reactor::Core;
+
+        let compressed = Encoding::Gzip.encode(b"decompressed");
+        let mut response = format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n", compressed.len()).into_bytes();
+        response.extend_from_slice(&compressed);
+        let response: &'static [u8] = Box::leak(response.into_boxed_slice());
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(serve(response).as_str())
+            .accept_gzip()
+            .auto_decompress(true)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"decompressed");
+        assert_eq!(result.header("content-encoding"), Some("gzip"));
+
+        let request = get(serve(response).as_str())
+            .auto_decompress(false)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), &compressed[..]);
+
+        let request = get(echo().as_str())
+            .auto_decompress(false)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(!result.body_str().unwrap().contains("Accept-Encoding:"));
+
+        if !Version::get().feature_brotli() {
+            return;
+        }
+        let request = get("https://httpbin.org/brotli")
+            .auto_decompress(true)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.header("Content-Encoding"), Some("br"));
+        assert!(result.body_str().unwrap().contains(r#""brotli": true"#));
+    }
+
+    #[test]
+    fn basic_auth() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/basic-auth/user/pass")
+            .basic_auth("user", Some("pass"))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 200);
+
+        let request = get("https://httpbin.org/basic-auth/user/pass")
+            .basic_auth("user", Some("wrong"))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 401);
+    }
+
+    #[test]
+    fn body() {
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let data = vec![b'x'; 1000];
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .body_with_type(data.clone(), "application/octet-stream".parse().unwrap())
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains(&format!("\"data\": \"{}\"", String::from_utf8(data).unwrap())));
+        assert!(body.contains("\"Content-Length\": \"1000\""));
+        assert!(body.contains("\"Content-Type\": \"application/octet-stream\""));
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn body_reader() {
+        use ::RequestError;
+        use ::str::put;
+        use serde_json::Value;
+        use std::io::{self, Cursor, Read};
+        use tokio_core::reactor::Core;
+
+        let data = vec![b'x'; 10 * 1024];
+        let mut evloop = Core::new().unwrap();
+        let request = put("https://httpbin.org/put")
+            .body_reader(Cursor::new(data.clone()), data.len() as u64)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let json: Value = result.json().expect("Failed to parse response.");
+        assert_eq!(json.pointer("/data").and_then(|v| v.as_str()).map(|data| data.len()), Some(data.len()));
+        assert_eq!(json.pointer("/headers/Content-Length").and_then(|v| v.as_str()), Some("10240"));
+
+        let request = put(echo().as_str())
+            .body_reader_chunked(Cursor::new(b"Hello".to_vec()))
+            .expect_continue(false)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echoed = result.body_str().unwrap();
+        assert!(echoed.starts_with("PUT / HTTP/1.1\r\n"));
+        assert!(echoed.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(echoed.ends_with("\r\n\r\n5\r\nHello\r\n0\r\n\r\n"));
+
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::InvalidData, "broken"))
+            }
+        }
+
+        let request = put(stall().as_str())
+            .body_reader(Broken, 10)
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Io(ref err)) if err.kind() == io::ErrorKind::InvalidData => {},
+            res => panic!("Expected a read error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn body_reader_chunked() {
+        use ::str::post;
+        use std::io::{self, Read};
+        use tokio_core::reactor::Core;
+
+        /// Yields its data in pieces of varying size, like a pipe would.
+        struct Pipe(Vec<u8>, usize);
+
+        impl Read for Pipe {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                let len = [buf.len(), self.0.len(), self.1 % 7 * 1000 + 1].iter().cloned().min().unwrap();
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0.drain(..len);
+                Ok(len)
+            }
+        }
+
+        let data: Vec<u8> = (0..100000u32).map(|i| (i % 251) as u8).collect();
+        let mut evloop = Core::new().unwrap();
+        let request = post(echo().as_str())
+            .header("Content-Length", "1")
+            .body_reader_chunked(Pipe(data.clone(), 0))
+            .expect_continue(false)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        let echoed = result.body();
+        let split = echoed.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let head = String::from_utf8_lossy(&echoed[..split]).to_ascii_lowercase();
+        assert!(head.contains("\r\ntransfer-encoding: chunked\r\n"));
+        assert!(!head.contains("content-length"));
+
+        let mut received = Vec::new();
+        let mut rest = &echoed[split..];
+        loop {
+            let line_end = rest.windows(2).position(|w| w == b"\r\n").unwrap();
+            let len = usize::from_str_radix(str::from_utf8(&rest[..line_end]).unwrap(), 16).unwrap();
+            received.extend_from_slice(&rest[line_end + 2..line_end + 2 + len]);
+            rest = &rest[line_end + 2 + len + 2..];
+            if len == 0 {
+                break;
+            }
+        }
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn build() {
+        use ::{Cookie, CookieJar, Encoding};
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let url = echo();
+        let jar = CookieJar::new();
+        jar.store(&url, Cookie::parse("lang=en").unwrap());
+        let request = post(url.as_str())
+            .param("page", "1")
+            .header("User-Agent", "my-app/1.0")
+            .cookie("session", "abc")
+            .cookie_jar(jar)
+            .body("data")
+            .compress_body(Encoding::Gzip);
+        let prepared = request.build();
+
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(request.send(evloop.handle())).expect("HTTP Request failed!");
+        let sent = result.body();
+        let head = String::from_utf8_lossy(&sent[..sent.windows(4).position(|w| w == b"\r\n\r\n").unwrap()]);
+
+        // Everything the prepared request contains is sent exactly like that
+        assert!(head.starts_with(&format!("POST /?{} HTTP/1.1\r\n", prepared.url().query().unwrap())));
+        for (name, value) in prepared.headers() {
+            assert!(head.contains(&format!("\r\n{}: {}\r\n", name, value)), "{} is not sent as prepared", name);
+        }
+        assert_eq!(head.matches("User-Agent").count(), 1);
+        assert!(sent.ends_with(prepared.body().unwrap()));
+    }
+
+    #[test]
+    fn bytes_transferred() {
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let url = respond(|_| {
+            let mut response = b"HTTP/1.1 200 OK\r\nContent-Length: 20000\r\n\r\n".to_vec();
+            response.extend(vec![b'x'; 20000]);
+            response
+        });
+        let mut evloop = Core::new().unwrap();
+        let request = post(url.as_str())
+            .body(vec![b'y'; 1000])
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert_eq!(result.bytes_downloaded(), 20000);
+        assert_eq!(result.bytes_uploaded(), 1000);
+        assert!(result.speed_download() > 0.0);
+        assert!(result.speed_upload() > 0.0);
+        assert!(result.speed_download() > result.speed_upload());
+    }
+
+    #[test]
+    fn ca_cert() {
+        use ::get;
+        use std::fs;
+        use tokio_core::reactor::Core;
+
+        const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_tls(RESPONSE);
+        assert!(evloop.run(get(&url).send(evloop.handle())).is_err());
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert_bytes(&fs::read(cert_path("ca.pem")).unwrap())
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        // The root is trusted, but the certificate is not issued for 127.0.0.1
+        let mut url = serve_tls(RESPONSE);
+        url.set_host(Some("127.0.0.1")).unwrap();
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+    }
+
+    #[test]
+    fn ciphers() {
+        use ::{get, RequestError, TlsVersion};
+        use tokio_core::reactor::Core;
+
+        const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .ciphers("ECDHE-RSA-AES128-GCM-SHA256:ECDHE-RSA-AES256-GCM-SHA384")
+            .max_tls_version(TlsVersion::Tls12)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .tls13_ciphers("TLS_AES_128_GCM_SHA256")
+            .min_tls_version(TlsVersion::Tls13)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ciphers("NOT-A-CIPHER")
+            .max_tls_version(TlsVersion::Tls12)
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Curl(err)) => assert!(err.is_ssl_cipher()),
+            other => panic!("Expected a cipher error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn client() {
+        use ::{Client, RequestError};
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+        use url::Url;
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle())
+            .default_header("X-Client", "tokio-request")
+            .default_timeout(Duration::from_secs(20));
+
+        let request = client.get(&Url::parse("https://httpbin.org/headers").unwrap());
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        assert!(result.is_success());
+        assert!(result.body_str().unwrap().contains("X-Client"));
+
+        let request = client.get(&Url::parse("https://httpbin.org/delay/5").unwrap())
+            .timeout(Duration::from_secs(1));
+        match evloop.run(client.send(request)) {
+            Err(RequestError::Timeout) => {},
+            res => panic!("Expected a timeout, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn client_accept_encoding() {
+        use ::{Client, Encoding};
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle()).accept_encoding(&[Encoding::Gzip]);
+
+        let result = evloop.run(client.send(client.get(&echo()))).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains("\r\nAccept-Encoding: gzip\r\n"));
+
+        let request = client.get(&echo()).accept_encoding(&[Encoding::Deflate]);
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains("\r\nAccept-Encoding: deflate\r\n"));
+
+        let request = client.get(&echo()).auto_decompress(false);
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        assert!(!result.body_str().unwrap().contains("Accept-Encoding:"));
+    }
+
+    #[test]
+    fn client_auth_provider() {
+        use ::Client;
+        use std::net::TcpListener;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::thread;
+        use tokio_core::reactor::Core;
+
+        // Rejects every token but the fresh one, one request per connection
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                handle(stream.unwrap(), |request| {
+                    if String::from_utf8_lossy(request).contains("\r\nAuthorization: Bearer fresh\r\n") {
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK".to_vec()
+                    } else {
+                        b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+                    }
+                });
+            }
+        });
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle()).auth_provider(move || {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 { "stale" } else { "fresh" }.to_owned()
+        }, true);
+        let request = client.send(client.get(&url));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert_eq!(result.status_code(), 200);
+        assert_eq!(result.body(), b"OK");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn client_default_header() {
+        use ::Client;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle())
+            .default_header("X-Api-Key", "old")
+            .default_header("x-api-key", "default")
+            .default_header("User-Agent", "tokio-request-client");
+
+        let request = client.get(&echo());
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        let echoed = result.body_str().unwrap().to_ascii_lowercase();
+        assert!(echoed.contains("\r\nx-api-key: default\r\n"));
+        assert!(!echoed.contains("x-api-key: old"));
+        assert!(echoed.contains("\r\nuser-agent: tokio-request-client\r\n"));
+
+        let request = client.get(&echo()).header("X-API-KEY", "override");
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        let echoed = result.body_str().unwrap().to_ascii_lowercase();
+        assert!(echoed.contains("\r\nx-api-key: override\r\n"));
+        assert!(!echoed.contains("x-api-key: default"));
+    }
+
+    #[test]
+    fn client_interceptor() {
+        use ::Client;
+        use std::sync::{Arc, Mutex};
+        use tokio_core::reactor::Core;
+
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let recorded = statuses.clone();
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle())
+            .with_interceptor(|request| request.header("X-Injected", "first"))
+            .with_interceptor(|request| request.append_header("X-Injected", "second"))
+            .with_observer(move |response| recorded.lock().unwrap().push(response.status_code()));
+        let result = evloop.run(client.send(client.get(&echo()))).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("\r\nX-Injected: first\r\nX-Injected: second\r\n"));
+        assert_eq!(*statuses.lock().unwrap(), vec![200]);
+    }
+
+    #[test]
+    fn client_cert() {
+        use ::{get, CertificateType};
+        use tokio_core::reactor::Core;
+
+        const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_mutual_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+
+        let url = serve_mutual_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .client_cert(&cert_path("client.pem"), Some(&cert_path("client.key")), Some("tokio-request"))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_mutual_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .client_cert(&cert_path("client.p12"), None, Some("tokio-request"))
+            .client_cert_type(CertificateType::P12)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+    }
+
+    #[test]
+    fn cookies() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/cookies")
+            .cookie("first", "1")
+            .cookies(vec![("second", "2"), ("third", "3")])
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("first"));
+        assert!(body.contains("second"));
+        assert!(body.contains("third"));
+    }
+
+    #[test]
+    fn cookie_jar() {
+        use ::{Client, CookieJar};
+        use tokio_core::reactor::Core;
+        use url::Url;
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle()).cookie_jar(CookieJar::new());
+
+        let request = client.get(&Url::parse("https://httpbin.org/cookies/set?session=abc").unwrap());
+        evloop.run(client.send(request)).expect("HTTP Request failed!");
+
+        let request = client.get(&Url::parse("https://httpbin.org/cookies").unwrap());
+        let result = evloop.run(client.send(request)).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains("abc"));
+    }
+
+    #[test]
+    fn cookie_jar_redirect() {
+        use ::{Client, CookieJar};
+        use tokio_core::reactor::Core;
+
+        let url = serve_sequence(&[
+            b"HTTP/1.1 302 Found\r\n\
+              Location: /home\r\n\
+              Set-Cookie: session=abc; Path=/\r\n\
+              Content-Length: 0\r\n\
+              Connection: close\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\n\
+              Set-Cookie: lang=en; Path=/home\r\n\
+              Content-Length: 2\r\n\
+              Connection: close\r\n\r\nOK"
+        ]);
+        let jar = CookieJar::new();
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle()).cookie_jar(jar.clone());
+        let result = evloop.run(client.send(client.get(&url).follow_redirects(true))).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 200);
+        assert_eq!(result.header_all("Set-Cookie"), vec!["lang=en; Path=/home"]);
+
+        // The cookie of the redirect is stored although only the final headers are kept
+        assert_eq!(jar.cookie_header(&url), Some("session=abc".to_owned()));
+        assert_eq!(jar.cookie_header(&url.join("/home").unwrap()), Some("session=abc; lang=en".to_owned()));
+    }
+
+    #[test]
+    fn compress_body() {
+        use ::Encoding;
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let data = "tokio-request ".repeat(100);
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .compress_body(Encoding::Gzip)
+            .text(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("\"Content-Encoding\": \"gzip\""));
+        assert!(!body.contains(&format!("\"Content-Length\": \"{}\"", data.len())));
+    }
+
+    #[test]
+    fn conditional_request() {
+        use ::str::get;
+        use std::time::SystemTime;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/cache").send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success() && !result.is_not_modified());
+
+        let request = get("https://httpbin.org/cache")
+            .if_none_match("tokio-request")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_not_modified());
+
+        let request = get("https://httpbin.org/cache")
+            .if_modified_since(SystemTime::now())
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_not_modified());
+    }
+
+    #[test]
+    fn configure() {
+        use ::get;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use tokio_core::reactor::Core;
+
+        let configured = Arc::new(AtomicBool::new(false));
+        let flag = configured.clone();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&echo())
+            .user_agent("tokio-request")
+            .configure(move |easy| {
+                flag.store(true, Ordering::SeqCst);
+                easy.tcp_nodelay(true)
+            })
+            .configure(|easy| easy.useragent("overridden"))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(configured.load(Ordering::SeqCst));
+        assert!(result.body_str().unwrap().contains("User-Agent: overridden\r\n"));
+    }
+
+    #[test]
+    fn connect_timeout() {
+        use ::RequestError;
+        use ::str::get;
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("http://10.255.255.1/")
+            .connect_timeout(Duration::from_secs(1))
+            .timeout(Duration::from_secs(20))
+            .send(evloop.handle());
+
+        match evloop.run(request) {
+            Err(RequestError::Timeout) => {},
+            res => panic!("Expected a timeout, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn connect_to() {
+        use ::{get, RequestError};
+        use tokio_core::reactor::Core;
+
+        let port = echo().port().unwrap();
+        let url = Url::parse("http://backend.tokio-request.invalid/").unwrap();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .connect_to("backend.tokio-request.invalid", 80, "127.0.0.1", port)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.body_str().unwrap().contains("Host: backend.tokio-request.invalid\r\n"));
+        assert_eq!(result.primary_port(), Some(port));
+
+        let request = get(&url)
+            .connect_to("backend.tokio-request.invalid", 80, "127.0.0.1\0", port)
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Curl(_)) => {},
+            res => panic!("Expected a cURL error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn connection_reuse() {
+        use ::Client;
+        use tokio_core::reactor::Core;
+
+        let url = serve_keep_alive(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK", 2);
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle());
+        let result = evloop.run(client.send(client.get(&url))).expect("HTTP Request failed!");
+        assert!(!result.reused_connection());
+
+        let result = evloop.run(client.send(client.get(&url))).expect("HTTP Request failed!");
+        assert!(result.reused_connection());
+        assert_eq!(result.body(), b"OK");
+    }
+
+    #[test]
+    fn connection_max_age() {
+        use ::Client;
+        use std::net::TcpListener;
+        use std::thread;
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        // Answers one request on each of two connections, keeping both open
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let streams: Vec<_> = listener.incoming()
+                .take(2)
+                .map(|stream| {
+                    let stream = stream.unwrap();
+                    handle(&stream, |_| b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK".to_vec());
+                    stream
+                })
+                .collect();
+            drop(streams);
+        });
+
+        let mut evloop = Core::new().unwrap();
+        let client = Client::new(evloop.handle()).max_connection_age(Duration::from_secs(1));
+        let result = evloop.run(client.send(client.get(&url))).expect("HTTP Request failed!");
+        assert!(!result.reused_connection());
+
+        // cURL compares the age in whole seconds
+        thread::sleep(Duration::from_millis(2100));
+        let result = evloop.run(client.send(client.get(&url))).expect("HTTP Request failed!");
+        assert!(!result.reused_connection());
+        assert_eq!(result.body(), b"OK");
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn custom_method() {
+        use ::{request, Method};
+        use ::str;
+        use std::collections::BTreeMap;
+        use tokio_core::reactor::Core;
+
+        let mut data = BTreeMap::new();
+        data.insert("depth".to_owned(), 1);
+
+        let mut evloop = Core::new().unwrap();
+        let request = request(&echo(), Method::Custom("REPORT".to_owned()))
+            .json(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.starts_with("REPORT / HTTP/1.1\r\n"));
+        assert!(body.contains("Content-Length: 11\r\n"));
+        assert!(body.ends_with("\r\n\r\n{\"depth\":1}"));
+
+        let request = str::request("https://httpbin.org/anything", Method::Custom("REPORT".to_owned()))
+            .json(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let json = result.json_value().expect("Failed to parse response.");
+
+        // Compares the encoded values, strings have no accessor common to both features
+        assert_eq!(json.find("method").map(|method| method.to_string()), Some(r#""REPORT""#.to_owned()));
+        assert_eq!(json.find("data").map(|data| data.to_string()), Some(r#""{\"depth\":1}""#.to_owned()));
+    }
+
+    #[test]
+    fn danger_accept_invalid_certs() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://self-signed.badssl.com/").send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+
+        let request = get("https://self-signed.badssl.com/")
+            .danger_accept_invalid_certs(true)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn debug_callback() {
+        use ::{get, DebugType};
+        use std::sync::{Arc, Mutex};
+        use tokio_core::reactor::Core;
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&echo())
+            .header("X-Debug", "yes")
+            .debug_callback(move |kind, data| sink.lock().unwrap().push((kind, data.to_vec())))
+            .send(evloop.handle());
+        evloop.run(request).expect("HTTP Request failed!");
+
+        let lines = lines.lock().unwrap();
+        assert!(lines.iter().any(|&(kind, ref data)| {
+            kind == DebugType::HeaderOut && String::from_utf8_lossy(data).contains("X-Debug: yes")
+        }));
+        assert!(lines.iter().any(|&(kind, ref data)| {
+            kind == DebugType::HeaderIn && data.starts_with(b"HTTP/1.1 200 OK")
+        }));
+        assert!(lines.iter().any(|&(kind, _)| kind == DebugType::DataIn));
+    }
+
+    #[test]
+    fn digest_auth() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/digest-auth/auth/user/pass")
+            .digest_auth("user", "pass")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+
+        let request = get("https://httpbin.org/digest-auth/auth/user/pass")
+            .digest_auth("user", "wrong")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 401);
+    }
+
+    #[test]
+    fn dns_cache_timeout() {
+        use ::{Client, DebugType};
+        use std::sync::{Arc, Mutex};
+        use tokio_core::reactor::Core;
+
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+        fn cache_hits(secs: i64) -> usize {
+            let port = serve_sequence(&[OK, OK]).port().unwrap();
+            let url = Url::parse(&format!("http://localhost:{}/", port)).unwrap();
+            let hits = Arc::new(Mutex::new(0));
+
+            let mut evloop = Core::new().unwrap();
+            let client = Client::new(evloop.handle());
+            for _ in 0..2 {
+                let counter = hits.clone();
+                let request = client.get(&url)
+                    .dns_cache_timeout(secs)
+                    .debug_callback(move |kind, data| {
+                        if kind == DebugType::Text && String::from_utf8_lossy(data).contains("found in DNS cache") {
+                            *counter.lock().unwrap() += 1;
+                        }
+                    });
+                evloop.run(client.send(request)).expect("HTTP Request failed!");
+            }
+
+            let hits = *hits.lock().unwrap();
+            hits
+        }
+
+        assert_eq!(cache_hits(-1), 1);
+        assert_eq!(cache_hits(60), 1);
+        assert_eq!(cache_hits(0), 0);
+    }
+
+    #[test]
+    fn dns_servers() {
+        use ::{get, RequestError};
+        use curl::Version;
+        use std::net::{Ipv4Addr, SocketAddr};
+        use tokio_core::reactor::Core;
+
+        // Depending on the version, cURL reports either of these without c-ares
+        const CURLE_NOT_BUILT_IN: u32 = 4;
+        const CURLE_UNKNOWN_OPTION: u32 = 48;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&echo())
+            .dns_servers(&[SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 53), "[::1]:5353".parse().unwrap()])
+            .send(evloop.handle());
+
+        match (Version::get().ares_version(), evloop.run(request)) {
+            (Some(_), Ok(result)) => assert!(result.is_success()),
+            (None, Err(RequestError::Curl(ref err))) if err.code() == CURLE_NOT_BUILT_IN || err.code() == CURLE_UNKNOWN_OPTION => {},
+            (_, res) => panic!("Unexpected result {:?}", res)
+        }
+    }
+
+    #[test]
+    fn effective_url() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/redirect-to?url=https%3A%2F%2Fhttpbin.org%2Fget")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.is_success());
+        assert_eq!(result.effective_url().as_str(), "https://httpbin.org/get");
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn empty_body() {
+        use ::{delete, post};
+        use ::str;
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = str::post("https://httpbin.org/post").send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let json: Value = result.json().expect("Failed to parse response.");
+        assert!(result.is_success());
+        assert_eq!(json.pointer("/data").and_then(|v| v.as_str()), Some(""));
+
+        let request = post(&echo()).send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains("Content-Length: 0\r\n"));
+
+        let request = delete(&echo()).empty_body().send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().starts_with("DELETE "));
+        assert!(result.body_str().unwrap().contains("Content-Length: 0\r\n"));
+    }
+
+    #[test]
+    fn form() {
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .param("query", "param")
+            .form(vec![("name", "tokio request"), ("lang", "Rust & C")])
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("\"name\": \"tokio request\""));
+        assert!(body.contains("\"lang\": \"Rust & C\""));
+        assert!(body.contains("\"query\": \"param\""));
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn form_struct() {
+        use ::str::post;
+        use serde::{Serialize, Serializer};
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        struct Signup {
+            name: &'static str,
+            age: u8,
+            referrer: Option<&'static str>
+        }
+
+        impl Serialize for Signup {
+            fn serialize<S: Serializer>(&self, serializer: &mut S) -> Result<(), S::Error> {
+                let mut state = serializer.serialize_struct("Signup", 3)?;
+                serializer.serialize_struct_elt(&mut state, "name", self.name)?;
+                serializer.serialize_struct_elt(&mut state, "age", self.age)?;
+                serializer.serialize_struct_elt(&mut state, "referrer", self.referrer)?;
+                serializer.serialize_struct_end(state)
+            }
+        }
+
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .form_struct(&Signup { name: "Rust & C", age: 8, referrer: None })
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+
+        assert_eq!(echo.pointer("/form/name").and_then(|v| v.as_str()), Some("Rust & C"));
+        assert_eq!(echo.pointer("/form/age").and_then(|v| v.as_str()), Some("8"));
+        assert_eq!(echo.pointer("/form/referrer"), None);
+        assert_eq!(echo.pointer("/headers/Content-Type").and_then(|v| v.as_str()),
+                   Some("application/x-www-form-urlencoded"));
+    }
+
+    #[test]
+    fn head() {
+        use ::str::head;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = head("https://httpbin.org/response-headers?Content-Length=4096")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.is_success());
+        assert_eq!(result.content_length(), Some(4096));
+        assert!(result.body().is_empty());
+    }
+
+    #[test]
+    fn host() {
+        use ::RequestError;
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(echo().as_str())
+            .host("example.com:8080")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("\r\nHost: example.com:8080\r\n"));
+        assert_eq!(body.matches("Host:").count(), 1);
+
+        let request = get(echo().as_str())
+            .host("example.com\r\nX-Injected: 1")
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::InvalidHost(ref host)) if host == "example.com\r\nX-Injected: 1" => {},
+            other => panic!("Unexpected result: {:?}", other)
+        }
+
+        let request = get("https://httpbin.org/headers")
+            .host("httpbin.org")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains(r#""Host": "httpbin.org""#));
+    }
+
+    #[test]
+    fn http_version() {
+        use ::{HttpVersion, str};
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = str::get("https://httpbin.org/get")
+            .http_version(HttpVersion::Http11)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.http_version(), Some(HttpVersion::Http11));
+
+        let request = str::get("https://www.google.com/")
+            .http_version(HttpVersion::Http2)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.http_version(), Some(HttpVersion::Http2));
+    }
+
+    #[test]
+    fn interface() {
+        use ::get;
+        use tokio_core::reactor::Core;
+
+        let url = echo();
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .interface("127.0.0.1")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+
+        let request = get(&url)
+            .interface("host!192.0.2.1")
+            .send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+    }
+
+    #[test]
+    fn ip_resolve() {
+        use ::{get, IpResolve};
+        use std::net::{IpAddr, Ipv4Addr};
+        use tokio_core::reactor::Core;
+
+        // "localhost" resolves to both 127.0.0.1 and ::1, but the server only
+        // listens on the former
+        let mut url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        url.set_host(Some("localhost")).unwrap();
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .ip_resolve(IpResolve::V4)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+        assert_eq!(result.primary_ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert_eq!(result.primary_port(), url.port());
+        assert_eq!(result.local_ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    }
+
+    // Written against the API both serialization features have in common
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn json() {
+        use ::str::post;
+        use std::collections::BTreeMap;
+        use tokio_core::reactor::Core;
+
+        let mut data = BTreeMap::new();
+        data.insert("a".to_owned(), 10);
+        data.insert("b".to_owned(), 15);
+
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .json(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo = result.json_value().expect("Failed to parse response.");
+        let echoed = echo.find("json").and_then(|json| json.as_object()).expect("Missing echoed JSON.");
+
+        assert_eq!(echoed.len(), 2);
+        assert_eq!(echoed.get("a").and_then(|a| a.as_i64()), Some(10));
+        assert_eq!(echoed.get("b").and_then(|b| b.as_i64()), Some(15));
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn json_error() {
+        use ::{str, RequestError};
+        use tokio_core::reactor::Core;
+
+        struct Unserializable;
+
+        #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+        impl ::rustc_serialize::Encodable for Unserializable {
+            fn encode<S: ::rustc_serialize::Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+                // JSON object keys must be strings
+                encoder.emit_map(1, |encoder| encoder.emit_map_elt_key(0, |encoder| encoder.emit_nil()))
+            }
+        }
+
+        #[cfg(feature = "serde-serialization")]
+        impl ::serde::Serialize for Unserializable {
+            fn serialize<S: ::serde::Serializer>(&self, _: &mut S) -> Result<(), S::Error> {
+                Err(::serde::ser::Error::custom("not serializable"))
+            }
+        }
+
+        let request = str::post("https://httpbin.org/post").json(&Unserializable);
+        assert!(request.to_curl_command().contains("-H 'Content-Type: application/json'"));
+
+        let mut evloop = Core::new().unwrap();
+        match evloop.run(request.send(evloop.handle())) {
+            #[cfg(feature = "serde-serialization")]
+            Err(RequestError::Encode(ref err)) if err.to_string().contains("not serializable") => {},
+            #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+            Err(RequestError::Encode(ref err)) if err.to_string().contains("BadHashmapKey") => {},
+            res => panic!("Expected an encoding error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn lowspeed_limit() {
+        use ::{get, RequestError};
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        let url = stall();
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .lowspeed_limit(1000, Duration::from_secs(1))
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::LowSpeed) => {},
+            res => panic!("Expected a lowspeed abort, got {:?}", res)
+        }
+
+        let request = get(&url)
+            .lowspeed_limit(1000, Duration::from_secs(5))
+            .timeout(Duration::from_millis(500))
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Timeout) => {},
+            res => panic!("Expected a timeout, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn max_body_size() {
+        use ::RequestError;
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/bytes/100000")
+            .max_body_size(1000)
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::BodyTooLarge) => {},
+            res => panic!("Expected a too large body, got {:?}", res)
+        }
+
+        let request = get("https://httpbin.org/bytes/1000")
+            .max_body_size(1000)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body().len(), 1000);
+    }
+
+    #[test]
+    fn max_recv_speed() {
+        use ::get;
+        use std::time::{Duration, Instant};
+        use tokio_core::reactor::Core;
+
+        fn respond_bytes() -> Url {
+            respond(|_| {
+                let mut response = b"HTTP/1.1 200 OK\r\nContent-Length: 32768\r\n\r\n".to_vec();
+                response.extend(vec![b'x'; 32768]);
+                response
+            })
+        }
+
+        let mut evloop = Core::new().unwrap();
+        let start = Instant::now();
+        let request = get(&respond_bytes()).send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let uncapped = start.elapsed();
+        assert_eq!(result.body().len(), 32768);
+
+        let start = Instant::now();
+        let request = get(&respond_bytes())
+            .max_recv_speed(16384)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let capped = start.elapsed();
+        assert_eq!(result.body().len(), 32768);
+
+        assert!(capped > Duration::from_millis(500));
+        assert!(capped > uncapped);
+    }
+
+    #[test]
+    fn max_redirects() {
+        use ::RequestError;
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/redirect/3")
+            .max_redirects(2)
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::TooManyRedirects) => {},
+            res => panic!("Expected too many redirects, got {:?}", res)
+        }
+
+        let request = get("https://httpbin.org/redirect/3")
+            .max_redirects(5)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn method_from_str() {
+        use ::Method;
+
+        assert_eq!("GET".parse::<Method>().unwrap(), Method::Get);
+        assert_eq!("post".parse::<Method>().unwrap(), Method::Post);
+        assert_eq!("Options".parse::<Method>().unwrap(), Method::Options);
+        assert_eq!("delete".parse::<Method>().unwrap(), Method::Delete);
+
+        let custom = "PURGE".parse::<Method>().unwrap();
+        assert_eq!(custom, Method::Custom("PURGE".to_owned()));
+        assert_eq!(custom.to_string().parse::<Method>().unwrap(), custom);
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn method_serde() {
+        use ::Method;
+        use serde_json;
+
+        assert_eq!(serde_json::to_string(&Method::Patch).unwrap(), "\"PATCH\"");
+        assert_eq!(serde_json::from_str::<Method>("\"head\"").unwrap(), Method::Head);
+
+        let custom = Method::Custom("PURGE".to_owned());
+        let json = serde_json::to_string(&custom).unwrap();
+        assert_eq!(serde_json::from_str::<Method>(&json).unwrap(), custom);
+    }
+
+    #[test]
+    fn multipart() {
+        use ::Multipart;
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let form = Multipart::new()
+            .text("name", "tokio-request")
+            .file("upload", "hello.txt", "Hello from Rust", "text/plain".parse().unwrap());
+
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .multipart(form)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.contains("\"name\": \"tokio-request\""));
+        assert!(body.contains("\"upload\": \"Hello from Rust\""));
+    }
+
+    #[test]
+    fn multipart_mime() {
+        use ::{Multipart, RequestError};
+        use curl::easy::Easy;
+        use ::str::post;
+        use tokio_core::reactor::Core;
+
+        let form = Multipart::new()
+            .text("name", "tokio-request")
+            .file("upload", "hello.txt", "Hello from Rust", "text/plain".parse().unwrap());
+
+        let mut evloop = Core::new().unwrap();
+        let request = post(echo().as_str())
+            .multipart(form)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        let boundary = body.lines()
+            .find(|line| line.starts_with("Content-Type: multipart/form-data; boundary="))
+            .map(|line| &line["Content-Type: multipart/form-data; boundary=".len()..])
+            .expect("Missing multipart Content-Type.");
+        assert!(body.contains(&format!("--{}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\ntokio-request\r\n", boundary)));
+        assert!(body.contains("Content-Disposition: form-data; name=\"upload\"; filename=\"hello.txt\"\r\n\
+                               Content-Type: text/plain\r\n\r\nHello from Rust\r\n"));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+
+        // The form is detached from the handle once the transfer completes
+        let request = post(echo().as_str())
+            .text("plain")
+            .use_handle(Easy::from(result))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+        assert!(body.contains("\r\nContent-Type: text/plain; charset=utf-8\r\n"));
+        assert!(!body.contains("multipart/form-data"));
+        assert!(body.ends_with("\r\n\r\nplain"));
+
+        // Invalid parts fail the request instead of panicking
+        let form = Multipart::new().text("na\0me", "tokio-request");
+        match evloop.run(post(echo().as_str()).multipart(form).send(evloop.handle())) {
+            Err(RequestError::Curl(_)) => {},
+            res => panic!("Expected a cURL error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn on_progress() {
+        use ::{get, RequestError};
+        use std::sync::{Arc, Mutex};
+        use tokio_core::reactor::Core;
+
+        let url = respond(|_| {
+            let mut response = b"HTTP/1.1 200 OK\r\nContent-Length: 100000\r\n\r\n".to_vec();
+            response.extend(vec![b'x'; 100000]);
+            response
+        });
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = progress.clone();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .on_progress(move |dltotal, dlnow, _, _| reported.lock().unwrap().push((dltotal, dlnow)))
+            .send(evloop.handle());
+        evloop.run(request).expect("HTTP Request failed!");
+
+        let progress = progress.lock().unwrap();
+        assert!(progress.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert_eq!(progress.last(), Some(&(100000, 100000)));
+
+        // A panicking function aborts the transfer instead of unwinding into cURL
+        let request = get(&serve(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK"))
+            .on_progress(|_, _, _, _| panic!("progress"))
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Curl(ref err)) if err.is_aborted_by_callback() => {},
+            res => panic!("Expected the transfer to be aborted, got {:?}", res)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn params() {
+        use ::str::get;
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/get")
+            .param("x", "1")
+            .param("x", "2")
+            .params(vec![("Hello & welcome", "This = Rust"), ("x", "3")])
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+
+        let values = echo.pointer("/args/x").and_then(|v| v.as_array()).unwrap();
+        assert_eq!(values.iter().map(|v| v.as_str().unwrap()).collect::<Vec<_>>(), vec!["1", "2", "3"]);
+        assert_eq!(echo.pointer("/args/Hello & welcome").and_then(|v| v.as_str()), Some("This = Rust"));
+        assert_eq!(result.effective_url().query(),
+                   Some("x=1&x=2&Hello+%26+welcome=This+%3D+Rust&x=3"));
+    }
+
+    #[test]
+    fn params_with_query() {
+        use ::get;
+        use tokio_core::reactor::Core;
+
+        let mut url = echo();
+        url.set_path("/path");
+        url.set_query(Some("a=1&b=%20x"));
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .param("a", "2")
+            .param("c", "3")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.body_str().unwrap().starts_with("GET /path?a=1&b=%20x&a=2&c=3 HTTP/1.1\r\n"));
+        assert_eq!(result.effective_url().query(), Some("a=1&b=%20x&a=2&c=3"));
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn patch() {
+        use ::str::patch;
+        use std::collections::BTreeMap;
+        use tokio_core::reactor::Core;
+
+        let mut data = BTreeMap::new();
+        data.insert("name".to_owned(), "patched".to_owned());
+
+        let mut evloop = Core::new().unwrap();
+        let request = patch(echo().as_str())
+            .json(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.starts_with("PATCH / HTTP/1.1\r\n"));
+        assert!(body.contains("Content-Type: application/json\r\n"));
+        assert!(body.ends_with("\r\n\r\n{\"name\":\"patched\"}"));
+
+        let request = patch("https://httpbin.org/patch")
+            .json(&data)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let json = result.json_value().expect("Failed to parse response.");
+        let echoed = json.find("json").and_then(|json| json.as_object()).expect("Missing echoed JSON.");
+
+        assert_eq!(echoed.len(), 1);
+        assert_eq!(echoed.get("name").map(|name| name.to_string()), Some(r#""patched""#.to_owned()));
+    }
+
+    #[test]
+    fn pin_public_key() {
+        use ::{get, RequestError};
+        use tokio_core::reactor::Core;
+
+        const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .pin_public_key("sha256//20VwOo/qW50ofUVBTAwP7sJ3wecLOHfRZM/aZH8lN7c=")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .pin_public_key("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=")
+            .pin_public_key("20VwOo/qW50ofUVBTAwP7sJ3wecLOHfRZM/aZH8lN7c=")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .pin_public_key("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=")
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Curl(err)) => assert_eq!(err.code(), 90),
+            other => panic!("Expected a pinning error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn primary_ip() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/get").send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.primary_ip().is_some_and(|ip| !ip.is_unspecified()));
+        assert_eq!(result.primary_port(), Some(443));
+        assert!(result.local_ip().is_some());
+        assert!(result.local_port().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn text() {
+        use ::str::post;
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        let text = "Grüße aus Rust – ✓ 日本語";
+        let mut evloop = Core::new().unwrap();
+        let request = post("https://httpbin.org/post")
+            .text(text)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+
+        assert_eq!(echo.pointer("/data").and_then(|v| v.as_str()), Some(text));
+        assert_eq!(echo.pointer("/headers/Content-Type").and_then(|v| v.as_str()),
+                   Some("text/plain; charset=utf-8"));
+    }
+
+    #[test]
+    fn proxy() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut proxy = echo();
+        proxy.set_username("user").unwrap();
+        proxy.set_password(Some("pa%20ss")).unwrap();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("http://example.com/path")
+            .proxy(&proxy)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+
+        assert!(body.starts_with("GET http://example.com/path HTTP/1.1\r\n"));
+        assert!(body.contains("Proxy-Authorization: Basic dXNlcjpwYSBzcw==\r\n"));
+    }
+
+    #[test]
+    fn proxy_bypass() {
+        use ::get;
+        use tokio_core::reactor::Core;
+
+        let url = echo();
+        let proxy = Url::parse("http://127.0.0.1:1/").unwrap();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .proxy(&proxy)
+            .no_proxy("localhost, 127.0.0.1")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.body_str().unwrap().starts_with("GET / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn proxy_tunnel() {
+        use ::str::get;
+        use std::sync::mpsc;
+        use tokio_core::reactor::Core;
+
+        let (tx, rx) = mpsc::channel();
+        let proxy = respond(move |request| {
+            tx.send(String::from_utf8_lossy(request).into_owned()).unwrap();
+            b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n".to_vec()
+        });
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://example.com/path")
+            .proxy(&proxy)
+            .proxy_basic_auth("user", "p@ss")
+            .send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+        let request = rx.recv().unwrap();
+
+        assert!(request.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwQHNz\r\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn query() {
+        use ::str::get;
+        use serde::{Serialize, Serializer};
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        struct Search {
+            term: &'static str,
+            page: u32
+        }
+
+        impl Serialize for Search {
+            fn serialize<S: Serializer>(&self, serializer: &mut S) -> Result<(), S::Error> {
+                let mut state = serializer.serialize_struct("Search", 2)?;
+                serializer.serialize_struct_elt(&mut state, "term", self.term)?;
+                serializer.serialize_struct_elt(&mut state, "page", self.page)?;
+                serializer.serialize_struct_end(state)
+            }
+        }
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/get")
+            .param("lang", "en")
+            .query(&Search { term: "tokio & curl", page: 2 })
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+
+        assert_eq!(echo.pointer("/args/lang").and_then(|v| v.as_str()), Some("en"));
+        assert_eq!(echo.pointer("/args/term").and_then(|v| v.as_str()), Some("tokio & curl"));
+        assert_eq!(echo.pointer("/args/page").and_then(|v| v.as_str()), Some("2"));
+        assert_eq!(result.effective_url().query(), Some("lang=en&term=tokio+%26+curl&page=2"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn query_encoding() {
+        use ::RequestError;
+        use ::str::{get, post};
+        use std::collections::BTreeMap;
+        use tokio_core::reactor::Core;
+
+        let mut map = BTreeMap::new();
+        map.insert("page", Some(2));
+        map.insert("limit", None);
+        let request = get("http://example.com/").param("lang", "en").query(&map).query(&[("x", "1 & 2")]);
+        assert_eq!(request.build().url().query(), Some("lang=en&page=2&x=1+%26+2"));
+
+        let request = post("http://example.com/").form_struct(&map);
+        assert_eq!(request.build().body(), Some(&b"page=2"[..]));
+
+        // Nested values cannot be encoded, which fails the request without panicking
+        let mut evloop = Core::new().unwrap();
+        let request = get("http://example.com/").query(&[("x", vec![1, 2])]);
+        match evloop.run(request.send(evloop.handle())) {
+            Err(RequestError::Encode(_)) => {},
+            res => panic!("Expected an encoding error, got {:?}", res)
+        }
+        let request = post("http://example.com/").form_struct(&"string");
+        match evloop.run(request.send(evloop.handle())) {
+            Err(RequestError::Encode(_)) => {},
+            res => panic!("Expected an encoding error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn range() {
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/range/1024")
+            .range(0, Some(99))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 206);
+        assert_eq!(result.body().len(), 100);
+
+        let request = get("https://httpbin.org/range/1024")
+            .range(1000, None)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 206);
+        assert_eq!(result.body().len(), 24);
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn referer() {
+        use ::str::get;
+        use serde_json::Value;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/headers")
+            .referer("https://example.com/page")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+        assert_eq!(echo.pointer("/headers/Referer").and_then(|v| v.as_str()), Some("https://example.com/page"));
+
+        let request = get("https://httpbin.org/headers")
+            .referer("https://example.com/page")
+            .header("Referer", "https://example.org/")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let echo: Value = result.json().expect("Failed to parse response.");
+        assert_eq!(echo.pointer("/headers/Referer").and_then(|v| v.as_str()), Some("https://example.org/"));
+    }
+
+    #[test]
+    fn resolve() {
+        use ::{get, RequestError};
+        use std::net::{IpAddr, Ipv4Addr};
+        use tokio_core::reactor::Core;
+
+        let port = echo().port().unwrap();
+        let url = Url::parse(&format!("http://tokio-request.invalid:{}/", port)).unwrap();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get(&url)
+            .resolve("tokio-request.invalid", port, IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .resolve("tokio-request.invalid", port, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.body_str().unwrap().contains(&format!("Host: tokio-request.invalid:{}\r\n", port)));
+
+        let request = get(&url)
+            .resolve("tokio-request\0.invalid", port, IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::Curl(_)) => {},
+            res => panic!("Expected a cURL error, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn retry() {
+        use ::{get, RetryPolicy};
+        use std::time::{Duration, Instant};
+        use tokio_core::reactor::Core;
+
+        const UNAVAILABLE: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_sequence(&[b"", UNAVAILABLE, OK]);
+        let start = Instant::now();
+        let request = get(&url)
+            .retry(RetryPolicy::new(3, Duration::from_millis(50)))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert_eq!(result.body(), b"OK");
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        let url = serve_sequence(&[UNAVAILABLE, UNAVAILABLE, OK]);
+        let request = get(&url)
+            .retry(RetryPolicy::new(2, Duration::from_millis(10)))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 503);
+    }
+
+    #[test]
+    fn retry_deadline() {
+        use ::{get, RetryPolicy};
+        use std::time::{Duration, Instant};
+        use tokio_core::reactor::Core;
+
+        const UNAVAILABLE: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_sequence(&[UNAVAILABLE; 10]);
+        let start = Instant::now();
+        let request = get(&url)
+            .retry(RetryPolicy::new(10, Duration::from_millis(50)).overall_deadline(Duration::from_millis(300)))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        // Retries start after 50 and 150ms, the next one would start after 350ms
+        assert_eq!(result.status_code(), 503);
+        assert!(start.elapsed() >= Duration::from_millis(150));
+        assert!(start.elapsed() < Duration::from_millis(300));
+    }
+
+    #[test]
+    fn retry_after() {
+        use ::{get, RetryPolicy};
+        use std::time::{Duration, Instant};
+        use tokio_core::reactor::Core;
+
+        const TOO_MANY: &[u8] = b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_sequence(&[TOO_MANY, OK]);
+        let start = Instant::now();
+        let request = get(&url)
+            .retry(RetryPolicy::new(2, Duration::from_millis(10))
+                .retry_statuses(&[429])
+                .respect_retry_after(true))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert_eq!(result.body(), b"OK");
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retry_after_too_long() {
+        use ::{get, RetryPolicy};
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        const TOO_MANY: &[u8] = b"HTTP/1.1 429 Too Many Requests\r\n\
+                                  Retry-After: 18446744073709551615\r\n\
+                                  Content-Length: 0\r\n\
+                                  Connection: close\r\n\r\n";
+        const UNAVAILABLE: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+        // The response asking for an absurd delay is returned instead of retried
+        let mut evloop = Core::new().unwrap();
+        let request = get(&serve_sequence(&[TOO_MANY]))
+            .retry(RetryPolicy::new(2, Duration::from_millis(10))
+                .retry_statuses(&[429])
+                .respect_retry_after(true))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 429);
+        assert_eq!(result.retry_after(), Some(Duration::from_secs(u64::MAX)));
+
+        // So is a response whose backoff overflows
+        let request = get(&serve_sequence(&[UNAVAILABLE]))
+            .retry(RetryPolicy::new(2, Duration::MAX))
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.status_code(), 503);
+    }
+
+    #[test]
+    fn send_all() {
+        use ::send_all;
+        use ::str::get;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let requests = (0..5).map(|i| get("https://httpbin.org/get").param("index", &i.to_string()));
+        let results = evloop.run(send_all(requests, evloop.handle())).unwrap();
+
+        assert_eq!(results.len(), 5);
+        for (i, result) in results.into_iter().enumerate() {
+            let result = result.expect("HTTP Request failed!");
+            assert!(result.is_success());
+            assert!(result.body_str().unwrap().contains(&format!("\"index\": \"{}\"", i)));
+        }
+    }
+
+    #[test]
+    fn send_cancelable() {
+        use ::{get, RequestError};
+        use futures::Future;
+        use std::time::{Duration, Instant};
+        use tokio_core::reactor::{Core, Timeout};
+
+        let mut evloop = Core::new().unwrap();
+        let (request, cancel) = get(&stall()).send_cancelable(evloop.handle());
+        let cancel_later = Timeout::new(Duration::from_millis(100), &evloop.handle()).unwrap();
+        evloop.handle().spawn(cancel_later.then(move |_| {
+            cancel.cancel();
+            Ok(())
+        }));
+
+        let start = Instant::now();
+        match evloop.run(request) {
+            Err(RequestError::Cancelled) => {},
+            res => panic!("Expected the request to be cancelled, got {:?}", res)
+        }
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        // Dropping the handle keeps the request alive
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let (request, cancel) = get(&url).send_cancelable(evloop.handle());
+        drop(cancel);
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn send_default() {
+        use ::str::get;
+
+        let result = get("https://httpbin.org/get")
+            .param("Hello", "Rust")
+            .send_default()
+            .expect("HTTP Request failed!");
+
+        assert!(result.is_success());
+        assert!(result.body_str().unwrap().contains("\"Hello\": \"Rust\""));
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn send_streaming() {
+        use ::str::get;
+        use futures::{Future, Stream};
+        use serde_json::{self, Value};
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/stream/5")
+            .send_streaming(evloop.handle())
+            .and_then(|(head, body)| body.collect().map(move |chunks| (head, chunks)));
+        let (head, chunks) = evloop.run(request).expect("HTTP Request failed!");
+        assert!(head.is_success());
+        assert!(!chunks.is_empty());
+
+        let body = chunks.concat();
+        let lines: Vec<Value> = body.split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).expect("Failed to parse line."))
+            .collect();
+        assert_eq!(lines.len(), 5);
+        for (id, line) in lines.iter().enumerate() {
+            assert_eq!(line.find("id").and_then(|id| id.as_u64()), Some(id as u64));
+        }
+    }
+
+    #[test]
+    fn send_streaming_errors() {
+        use ::{get, RequestError};
+        use futures::{Future, Stream};
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_sequence(&[b""]);
+        match evloop.run(get(&url).send_streaming(evloop.handle())) {
+            Err(RequestError::Curl(ref err)) if err.is_got_nothing() => {},
+            res => panic!("Expected the transfer to fail, got {:?}", res.map(|(head, _)| head))
+        }
+
+        // The connection breaks down after half of the body
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nHello");
+        let request = get(&url)
+            .send_streaming(evloop.handle())
+            .and_then(|(head, body)| {
+                assert_eq!(head.status_code(), 200);
+                assert_eq!(head.header("content-length"), Some("10"));
+                body.collect()
+            });
+        match evloop.run(request) {
+            Err(RequestError::Curl(ref err)) if err.is_partial_file() => {},
+            res => panic!("Expected the body stream to fail, got {:?}", res)
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn send_streaming_json_lines() {
+        use ::str::get;
+        use futures::{Future, Stream};
+        use serde::{Deserialize, Deserializer};
+        use serde::de::Error;
+        use serde_json::Value;
+        use std::collections::BTreeMap;
+        use tokio_core::reactor::Core;
+
+        #[derive(Debug)]
+        struct Line {
+            id: u64,
+            url: String
+        }
+
+        impl Deserialize for Line {
+            fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, D::Error> {
+                let mut map = BTreeMap::<String, Value>::deserialize(deserializer)?;
+                let id = map.remove("id").and_then(|id| id.as_u64()).ok_or_else(|| D::Error::missing_field("id"))?;
+                let url = match map.remove("url") {
+                    Some(Value::String(url)) => url,
+                    _ => return Err(D::Error::missing_field("url"))
+                };
+                Ok(Line { id, url })
+            }
+        }
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/stream/3")
+            .send_streaming(evloop.handle())
+            .and_then(|(head, body)| body.json_lines::<Line>().collect().map(move |lines| (head, lines)));
+        let (head, lines) = evloop.run(request).expect("HTTP Request failed!");
+        assert!(head.is_success());
+        assert_eq!(lines.len(), 3);
+        for (id, line) in lines.iter().enumerate() {
+            assert_eq!(line.id, id as u64);
+            assert_eq!(line.url, "https://httpbin.org/stream/3");
+        }
+    }
+
+    #[test]
+    fn send_to() {
+        use ::str::get;
+        use std::env;
+        use std::fs::{self, File};
+        use tokio_core::reactor::Core;
+
+        let path = env::temp_dir().join("tokio-request-send_to");
+        let file = File::create(&path).unwrap();
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/bytes/1000000").send_to(evloop.handle(), file);
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert!(result.is_success());
+        assert!(result.body().is_empty());
+        assert_eq!(fs::metadata(&path).unwrap().len(), 1000000);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn timeout() {
+        use ::RequestError;
+        use ::str::get;
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/delay/5")
+            .timeout(Duration::from_secs(1))
+            .send(evloop.handle());
+
+        match evloop.run(request) {
+            Err(RequestError::Timeout) => {},
+            res => panic!("Expected a timeout, got {:?}", res)
+        }
+    }
+
+    #[test]
+    fn timing() {
+        use ::str::get;
+        use std::time::Duration;
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/get").send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let timing = result.timing();
+
+        assert!(timing.total > Duration::from_secs(0));
+        assert!(timing.namelookup <= timing.connect);
+        assert!(timing.connect <= timing.appconnect);
+        assert!(timing.appconnect <= timing.pretransfer);
+        assert!(timing.pretransfer <= timing.starttransfer);
+        assert!(timing.starttransfer <= timing.total);
+    }
+
+    #[test]
+    fn tls_version() {
+        use ::{get, TlsVersion};
+        use tokio_core::reactor::Core;
+
+        const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK";
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve_tls(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .min_tls_version(TlsVersion::Tls13)
+            .max_tls_version(TlsVersion::Tls13)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls12(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .min_tls_version(TlsVersion::Tls12)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert_eq!(result.body(), b"OK");
+
+        let url = serve_tls12(RESPONSE);
+        let request = get(&url)
+            .ca_cert(&cert_path("ca.pem"))
+            .min_tls_version(TlsVersion::Tls13)
+            .send(evloop.handle());
+        assert!(evloop.run(request).is_err());
+    }
+
+    #[test]
+    fn try_str() {
+        use ::{Method, RequestError};
+        use ::str::{try_delete, try_get, try_post, try_put};
+
+        for result in [try_get("not a url"), try_post("http://"), try_put("://missing-scheme"), try_delete("http://[::1")] {
+            match result {
+                Err(RequestError::UrlParse(_)) => {},
+                res => panic!("Expected a URL parse error, got {:?}", res)
+            }
+        }
+
+        let request = try_post("https://httpbin.org/post").unwrap();
+        assert_eq!(request.to_string(), "POST https://httpbin.org/post");
+        let request = try_delete("https://httpbin.org/delete").unwrap();
+        assert_eq!(request.to_string(), format!("{} https://httpbin.org/delete", Method::Delete));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_socket() {
+        use ::str::get;
+        use std::env;
+        use std::fs;
+        use std::os::unix::net::UnixListener;
+        use std::process;
+        use std::thread;
+        use tokio_core::reactor::Core;
+
+        let path = env::temp_dir().join(format!("tokio-request-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle(stream, |request| {
+                let mut response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", request.len()).into_bytes();
+                response.extend_from_slice(request);
+                response
+            });
+        });
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("http://localhost/version")
+            .unix_socket(&path)
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_success());
+        assert!(result.body_str().unwrap().starts_with("GET /version HTTP/1.1\r\nHost: localhost\r\n"));
+        assert_eq!(result.primary_ip(), None);
+    }
+
+    #[test]
+    fn url_parse() {
+        use ::{Method, RequestError};
+        use ::str::try_request;
+        use std::error::Error;
+        use url::ParseError;
+
+        match try_request("not a url", Method::Get) {
+            Err(err @ RequestError::UrlParse(ParseError::RelativeUrlWithoutBase)) => assert!(err.source().is_some()),
+            res => panic!("Expected a URL parse error, got {:?}", res)
+        }
+        assert!(try_request("https://httpbin.org/get", Method::Get).is_ok());
+    }
+
+    #[test]
+    fn user_agent() {
+        use ::{str, USER_AGENT};
+        use tokio_core::reactor::Core;
+
+        let mut evloop = Core::new().unwrap();
+        let request = str::get("https://httpbin.org/user-agent").send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        assert!(result.body_str().unwrap().contains(&format!("\"{}\"", USER_AGENT)));
+
+        let request = str::get("https://httpbin.org/user-agent")
+            .user_agent("my-app/1.0")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+        let body = result.body_str().unwrap();
+        assert!(body.contains("\"my-app/1.0\""));
+        assert!(!body.contains(USER_AGENT));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+//! The module that contains the `multipart/form-data` body builder.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use curl::Error as CurlError;
+use curl::easy::{Easy, SeekResult};
+use curl_sys;
+use mime::Mime;
+
+/// The opaque `curl_mime` of cURL's MIME API, which `curl-sys` doesn't declare.
+enum CurlMime {}
+/// The opaque `curl_mimepart` of cURL's MIME API.
+enum CurlMimePart {}
+
+extern "C" {
+    fn curl_mime_init(easy: *mut curl_sys::CURL) -> *mut CurlMime;
+    fn curl_mime_free(mime: *mut CurlMime);
+    fn curl_mime_addpart(mime: *mut CurlMime) -> *mut CurlMimePart;
+    fn curl_mime_name(part: *mut CurlMimePart, name: *const c_char) -> curl_sys::CURLcode;
+    fn curl_mime_filename(part: *mut CurlMimePart, filename: *const c_char) -> curl_sys::CURLcode;
+    fn curl_mime_type(part: *mut CurlMimePart, mimetype: *const c_char) -> curl_sys::CURLcode;
+    fn curl_mime_data(part: *mut CurlMimePart, data: *const c_char, size: usize) -> curl_sys::CURLcode;
+}
+
+const CURLOPT_MIMEPOST: curl_sys::CURLoption = curl_sys::CURLOPTTYPE_OBJECTPOINT + 269;
+
+/// Represents a `multipart/form-data` request body.
+///
+/// See [`Request::multipart`](struct.Request.html#method.multipart)
+/// on how to send it.
+#[derive(Clone, Debug, Default)]
+pub struct Multipart {
+    parts: Vec<Part>
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    File(String, String, Vec<u8>, Mime),
+    Text(String, String)
+}
+
+impl Multipart {
+    /// Creates a new, empty `Multipart` body.
+    pub fn new() -> Self {
+        Multipart::default()
+    }
+
+    /// Adds a file field with the given file name, contents and
+    /// content type.
+    pub fn file<B: Into<Vec<u8>>>(mut self, name: &str, filename: &str, bytes: B, mime: Mime) -> Self {
+        self.parts.push(Part::File(name.to_owned(), filename.to_owned(), bytes.into(), mime));
+        self
+    }
+
+    /// Adds a text field.
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.parts.push(Part::Text(name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Sets the body as the MIME post of the given handle.
+    ///
+    /// cURL generates the boundary and the `Content-Type` header itself. The
+    /// returned `MimePost` must be detached from the handle once the transfer
+    /// has completed.
+    pub(crate) fn attach(&self, easy: &mut Easy) -> Result<MimePost, CurlError> {
+        let mime = MimeHandle(unsafe { curl_mime_init(easy.raw()) });
+        if mime.0.is_null() {
+            return Err(CurlError::new(curl_sys::CURLE_OUT_OF_MEMORY));
+        }
+
+        for part in &self.parts {
+            let handle = unsafe { curl_mime_addpart(mime.0) };
+            if handle.is_null() {
+                return Err(CurlError::new(curl_sys::CURLE_OUT_OF_MEMORY));
+            }
+            match *part {
+                Part::File(ref name, ref filename, ref bytes, ref mime) => {
+                    let filename = CString::new(filename.as_str())?;
+                    let mime = CString::new(mime.to_string())?;
+                    check(unsafe { curl_mime_name(handle, CString::new(name.as_str())?.as_ptr()) })?;
+                    check(unsafe { curl_mime_filename(handle, filename.as_ptr()) })?;
+                    check(unsafe { curl_mime_type(handle, mime.as_ptr()) })?;
+                    check(unsafe { curl_mime_data(handle, bytes.as_ptr() as *const c_char, bytes.len()) })?;
+                },
+                Part::Text(ref name, ref value) => {
+                    check(unsafe { curl_mime_name(handle, CString::new(name.as_str())?.as_ptr()) })?;
+                    check(unsafe { curl_mime_data(handle, value.as_ptr() as *const c_char, value.len()) })?;
+                }
+            }
+        }
+
+        check(unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_MIMEPOST, mime.0 as *mut c_void) })?;
+
+        // cURL only borrows the MIME structure. A cancelled transfer is only removed
+        // from the session some time after its future was dropped, so the handle keeps
+        // a reference to the structure until then. The seek callback is never called
+        // for MIME posts, cURL rewinds them itself.
+        let post = MimePost(Arc::new(Mutex::new(Some(mime))));
+        let guard = post.0.clone();
+        easy.seek_function(move |_| {
+            let _ = &guard;
+            SeekResult::CantSeek
+        })?;
+        Ok(post)
+    }
+}
+
+/// A MIME structure attached to a handle as its `CURLOPT_MIMEPOST`.
+pub(crate) struct MimePost(Arc<Mutex<Option<MimeHandle>>>);
+
+impl MimePost {
+    /// Resets the MIME post of the given handle and frees the structure, so the
+    /// handle can be reused for other requests.
+    pub(crate) fn detach(self, easy: &mut Easy) {
+        unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_MIMEPOST, ptr::null_mut::<c_void>()) };
+        self.0.lock().unwrap().take();
+    }
+}
+
+/// Owns a `curl_mime` and frees it when dropped.
+struct MimeHandle(*mut CurlMime);
+
+// The structure is only accessed by the handle it is attached to
+unsafe impl Send for MimeHandle {}
+
+impl Drop for MimeHandle {
+    fn drop(&mut self) {
+        unsafe { curl_mime_free(self.0) }
+    }
+}
+
+/// Turns the return code of a MIME API call into a `Result`.
+fn check(code: curl_sys::CURLcode) -> Result<(), CurlError> {
+    match code {
+        curl_sys::CURLE_OK => Ok(()),
+        code => Err(CurlError::new(code))
+    }
+}
@@ -0,0 +1,77 @@
+//! The module that contains the percent-encoding options of URL parameters.
+
+use url::Url;
+use url::form_urlencoded::byte_serialize;
+
+/// Represents the characters that are percent-encoded in URL parameters.
+///
+/// See [`Request::encoding_set`](struct.Request.html#method.encoding_set)
+/// for more information.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EncodingSet {
+    /// Encodes names and values like an `application/x-www-form-urlencoded` form,
+    /// only leaving alphanumeric characters and `*-._` alone. Spaces become `+`.
+    #[default]
+    Strict,
+    /// Like `Strict`, but leaves `,` and `:` alone.
+    Relaxed,
+    /// Does not encode anything, for names and values that already are encoded.
+    ///
+    /// Characters that are never valid in a query, e.g. spaces, are still encoded.
+    Raw
+}
+
+/// Appends the given parameters to the query of the given URL.
+pub(crate) fn append_params(url: &mut Url, params: &[(String, String)], set: EncodingSet) {
+    if params.is_empty() {
+        return;
+    }
+    if set == EncodingSet::Strict {
+        let mut query_pairs = url.query_pairs_mut();
+        for (key, value) in params {
+            query_pairs.append_pair(key.trim(), value.trim());
+        }
+        return;
+    }
+
+    let mut query = url.query().unwrap_or("").to_owned();
+    for (key, value) in params {
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&encode(key.trim(), set));
+        query.push('=');
+        query.push_str(&encode(value.trim(), set));
+    }
+    url.set_query(Some(&query));
+}
+
+fn encode(s: &str, set: EncodingSet) -> String {
+    match set {
+        EncodingSet::Strict => byte_serialize(s.as_bytes()).collect(),
+        EncodingSet::Relaxed => byte_serialize(s.as_bytes()).collect::<String>()
+            .replace("%2C", ",")
+            .replace("%3A", ":"),
+        EncodingSet::Raw => s.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_params, EncodingSet};
+    use url::Url;
+
+    fn query(set: EncodingSet) -> String {
+        let mut url = Url::parse("https://httpbin.org/get?page=1").unwrap();
+        let params = vec![("path".to_owned(), "a b,c:d/e%20".to_owned())];
+        append_params(&mut url, &params, set);
+        url.query().unwrap().to_owned()
+    }
+
+    #[test]
+    fn encoding_sets() {
+        assert_eq!(query(EncodingSet::Strict), "page=1&path=a+b%2Cc%3Ad%2Fe%2520");
+        assert_eq!(query(EncodingSet::Relaxed), "page=1&path=a+b,c:d%2Fe%2520");
+        assert_eq!(query(EncodingSet::Raw), "page=1&path=a%20b,c:d/e%20");
+    }
+}
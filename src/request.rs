@@ -1,27 +1,59 @@
 //! The module that contains the request code.
 
+use std::convert::TryFrom;
+use std::env;
+use std::error::Error;
+use std::ffi::CString;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
-use std::io::Error;
+use std::net::{IpAddr, SocketAddr};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::raw::{c_int, c_long, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::channel;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-use Method;
+use {HttpVersion, IpResolve, Method, TlsVersion};
 
-use curl::easy::{Easy, List};
-use futures::{BoxFuture, failed, Future};
-use response::Response;
-use tokio_core::reactor::Handle;
+use cookie::CookieJar;
+use debug::DebugType;
+use curl::Error as CurlError;
+use curl::easy::{Auth, Easy, HttpVersion as CurlHttpVersion, IpResolve as CurlIpResolve, List, ReadError, SslVersion};
+use curl_sys;
+use encoding::Encoding;
+use error::RequestError;
+use futures::future::{self, Either, Loop};
+use futures::{failed, Future, Stream};
+use futures::sync::{mpsc, oneshot};
+use httpdate::fmt_http_date;
+use mime::{Attr, Mime, SubLevel, TopLevel, Value};
+use multipart::Multipart;
+use query::{append_params, EncodingSet};
+use response::{hop_cookies, Response, ResponseHead};
+use retry::RetryPolicy;
+use stream::{BodyStream, ChunkWriter, StreamingFuture};
+use tls::CertificateType;
+use tokio_core::reactor::{Core, Handle, Timeout};
 use tokio_curl::Session;
 use url::Url;
+use url::form_urlencoded::Serializer;
+use url::percent_encoding::percent_decode;
 
-#[cfg(feature = "rustc-serialization")]
+#[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
 use rustc_serialize;
 
 #[cfg(feature = "serde-serialization")]
 use serde;
 #[cfg(feature = "serde-serialization")]
 use serde_json;
+#[cfg(feature = "serde-serialization")]
+use serde_urlencoded;
+#[cfg(feature = "serde-serialization")]
+use url::form_urlencoded;
 
 /// The default low byte rate threshold.
 ///
@@ -42,47 +74,560 @@ pub const LOW_SPEED_TIME: u32 = 10;
 /// for more information.
 pub const MAX_REDIRECTS: u32 = 10;
 
+/// The `User-Agent` sent along with every request unless overridden.
+///
+/// See [`Request::user_agent`](struct.Request.html#method.user_agent)
+/// for more information.
+pub const USER_AGENT: &str = concat!("tokio-request/", env!("CARGO_PKG_VERSION"));
+
+/// The placeholder for secrets in redacted `curl` command lines.
+const REDACTED: &str = "<redacted>";
+
+/// The future returned when sending a [`Request`](struct.Request.html).
+pub type ResponseFuture = Box<dyn Future<Item = Response, Error = RequestError>>;
+
+/// The future returned when sending a batch of requests through [`send_all`](fn.send_all.html).
+pub type BatchFuture = Box<dyn Future<Item = Vec<Result<Response, RequestError>>, Error = RequestError>>;
+
+/// The function configuring the cURL handle of a request.
+type ConfigureFn = dyn Fn(&mut Easy) -> Result<(), CurlError> + Send + Sync;
+
+/// The function called with cURL's verbose debug output.
+type DebugFn = dyn Fn(DebugType, &[u8]) + Send + Sync;
+
+/// The function called with the progress of a transfer.
+type ProgressFn = dyn Fn(u64, u64, u64, u64) + Send + Sync;
+
+/// The HTTP authentication scheme of a request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AuthScheme {
+    Basic,
+    Digest
+}
+
 /// Represents an HTTP request.
 ///
 /// While this can be used directly (and _must_ be for special HTTP verbs, it is
 /// preferred to use the [`get`](fn.get.html), [`post`](fn.post.html), etc. functions
 /// since they are shorter.
 pub struct Request {
+    accept_encoding: Option<Vec<Encoding>>,
+    accept_invalid_certs: bool,
+    auth: Option<(AuthScheme, String, String)>,
     body: Option<Vec<u8>>,
+    body_encoding: Option<Encoding>,
+    body_error: Option<Box<dyn Error + Send + Sync>>,
+    body_reader: Option<Arc<Mutex<dyn Read + Send>>>,
+    ca_cert: Option<PathBuf>,
+    ca_cert_bytes: Option<Vec<u8>>,
+    ciphers: Option<String>,
+    client_cert: Option<(PathBuf, Option<PathBuf>, Option<String>)>,
+    client_cert_type: CertificateType,
+    configure: Vec<Arc<ConfigureFn>>,
+    connect_timeout: Option<Duration>,
+    content_length: Option<u64>,
+    connect_to: Vec<String>,
+    cookie_jar: Option<CookieJar>,
+    cookies: Vec<(String, String)>,
+    debug_callback: Option<Arc<DebugFn>>,
+    decompress: bool,
+    dns_cache_timeout: Option<i64>,
+    dns_servers: Option<String>,
+    encoding_set: EncodingSet,
     follow_redirects: bool,
     handle: Option<Easy>,
     headers: Vec<(String, String)>,
+    http_version: Option<HttpVersion>,
+    interface: Option<String>,
+    invalid_host: Option<String>,
+    ip_resolve: IpResolve,
     lowspeed_limits: Option<(u32, Duration)>,
+    max_body_size: Option<usize>,
+    max_recv_speed: u64,
     max_redirects: u32,
+    max_send_speed: u64,
+    max_tls_version: Option<TlsVersion>,
     method: Method,
+    min_tls_version: Option<TlsVersion>,
+    multipart: Option<Multipart>,
+    no_proxy: Option<String>,
+    on_progress: Option<Arc<ProgressFn>>,
     params: Vec<(String, String)>,
+    pinned_public_keys: Vec<String>,
+    proxy: Option<Url>,
+    proxy_credentials: Option<(String, String)>,
+    query_error: Option<Box<dyn Error + Send + Sync>>,
+    range: Option<String>,
+    referer: Option<String>,
+    resolve: Vec<String>,
+    retry: Option<RetryPolicy>,
     timeout: Option<Duration>,
-    url: Url
+    tls13_ciphers: Option<String>,
+    unix_socket: Option<PathBuf>,
+    url: Url,
+    user_agent: String,
+    verbose: bool
 }
 
 impl Request {
     /// Creates a new instance of `Request`.
     pub fn new(url: &Url, method: Method) -> Self {
         Request {
+            accept_encoding: None,
+            accept_invalid_certs: false,
+            auth: None,
             body: None,
+            body_encoding: None,
+            body_error: None,
+            body_reader: None,
+            ca_cert: None,
+            ca_cert_bytes: None,
+            ciphers: None,
+            client_cert: None,
+            client_cert_type: CertificateType::default(),
+            configure: Vec::new(),
+            connect_timeout: None,
+            content_length: None,
+            connect_to: Vec::new(),
+            cookie_jar: None,
+            cookies: Vec::new(),
+            debug_callback: None,
+            decompress: true,
+            dns_cache_timeout: None,
+            dns_servers: None,
+            encoding_set: EncodingSet::default(),
             follow_redirects: true,
             handle: None,
             headers: Vec::new(),
+            http_version: None,
+            interface: None,
+            invalid_host: None,
+            ip_resolve: IpResolve::default(),
             lowspeed_limits: Some((LOW_SPEED_LIMIT, Duration::from_secs(LOW_SPEED_TIME as u64))),
+            max_body_size: None,
+            max_recv_speed: 0,
             max_redirects: MAX_REDIRECTS,
-            method: method,
+            max_send_speed: 0,
+            max_tls_version: None,
+            method,
+            min_tls_version: None,
+            multipart: None,
+            no_proxy: None,
+            on_progress: None,
             params: Vec::new(),
+            pinned_public_keys: Vec::new(),
+            proxy: None,
+            proxy_credentials: None,
+            query_error: None,
+            range: None,
+            referer: None,
+            resolve: Vec::new(),
+            retry: None,
             timeout: None,
-            url: url.clone()
+            tls13_ciphers: None,
+            unix_socket: None,
+            url: url.clone(),
+            user_agent: USER_AGENT.to_owned(),
+            verbose: false
         }
     }
 
+    /// Sets the `Accept` header to the given content type, replacing any
+    /// `Accept` header set before.
+    pub fn accept(self, mime: Mime) -> Self {
+        self.header("Accept", &mime.to_string())
+    }
+
+    /// Sets the encodings the server may use to compress the response, in order
+    /// of preference.
+    ///
+    /// cURL transparently decompresses the response, so the
+    /// [`Response::body`](struct.Response.html#method.body) always contains the
+    /// decompressed bytes. By default, all encodings cURL supports are accepted.
+    /// This turns [`auto_decompress`](#method.auto_decompress) back on.
+    pub fn accept_encoding(mut self, encodings: &[Encoding]) -> Self {
+        self.accept_encoding = Some(encodings.to_vec());
+        self.decompress = true;
+        self
+    }
+
+    /// Only accepts gzip-compressed responses (or uncompressed ones).
+    ///
+    /// See [`Request::accept_encoding`](#method.accept_encoding) for more information.
+    pub fn accept_gzip(self) -> Self {
+        self.accept_encoding(&[Encoding::Gzip])
+    }
+
+    /// Only accepts JSON responses by setting the `Accept` header to `application/json`.
+    ///
+    /// See [`Request::accept`](#method.accept) for more information.
+    pub fn accept_json(self) -> Self {
+        self.accept(Mime(TopLevel::Application, SubLevel::Json, vec![]))
+    }
+
+    /// Adds an HTTP header to the request, keeping the headers with the same
+    /// name set before.
+    ///
+    /// See [`Request::header`](#method.header) to replace them instead.
+    pub fn append_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Sets the option whether to negotiate compression with the server and
+    /// transparently decompress the response.
+    ///
+    /// When enabled, which is the default, all encodings cURL supports (e.g.
+    /// gzip, deflate, brotli and zstd, depending on how it was built) are
+    /// accepted, and [`Response::body`](struct.Response.html#method.body)
+    /// contains the decompressed bytes. The `Content-Encoding` header of the
+    /// response still reports the encoding used by the server. When disabled,
+    /// no `Accept-Encoding` header is sent and the body is returned as received.
+    pub fn auto_decompress(mut self, enabled: bool) -> Self {
+        self.accept_encoding = None;
+        self.decompress = enabled;
+        self
+    }
+
+    /// Authenticates the request using HTTP basic authentication.
+    ///
+    /// If no password is given, only the username (followed by a colon)
+    /// is sent.
+    pub fn basic_auth(mut self, username: &str, password: Option<&str>) -> Self {
+        self.auth = Some((AuthScheme::Basic, username.to_owned(), password.unwrap_or("").to_owned()));
+        self
+    }
+
+    /// Authenticates the request using the given bearer token.
+    ///
+    /// This sets the `Authorization` header to `Bearer <token>`, replacing
+    /// any `Authorization` header set previously.
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
     /// Sets the body of the request as raw byte array.
+    ///
+    /// This replaces any previously set body.
     pub fn body<B: Into<Vec<u8>>>(mut self, body: B) -> Self {
         self.body = Some(body.into());
+        self.body_error = None;
+        self.body_reader = None;
+        self.multipart = None;
+        self
+    }
+
+    /// Streams the request body of the given length from the given reader instead
+    /// of buffering it in memory, e.g. to upload a file.
+    ///
+    /// This replaces any previously set body. The reader is shared between clones
+    /// of the request and cannot be replayed, so the request is never
+    /// [retried](#method.retry). An error reading from it aborts the transfer and
+    /// resolves the future to [`RequestError::Io`](enum.RequestError.html#variant.Io).
+    pub fn body_reader<R: Read + Send + 'static>(self, reader: R, len: u64) -> Self {
+        self.body_reader_chunked(reader).content_length(len)
+    }
+
+    /// Streams the request body of unknown length from the given reader using
+    /// chunked transfer encoding, e.g. to upload data piped from another process.
+    ///
+    /// The body is sent until the reader is exhausted. A `Content-Length` header set
+    /// through [`header`](#method.header) is left out, declare the length through
+    /// [`content_length`](#method.content_length) instead if it becomes known before
+    /// sending. HTTP/2 frames the body on its own, so chunked transfer encoding is
+    /// only used with HTTP/1.1. See [`body_reader`](#method.body_reader) for more
+    /// information.
+    pub fn body_reader_chunked<R: Read + Send + 'static>(mut self, reader: R) -> Self {
+        self.body = None;
+        self.body_error = None;
+        self.body_reader = Some(Arc::new(Mutex::new(reader)));
+        self.content_length = None;
+        self.multipart = None;
+        self
+    }
+
+    /// Sets the body of the request as raw byte array and sets the
+    /// `Content-Type` to the given MIME type.
+    ///
+    /// This replaces any previously set body.
+    pub fn body_with_type<B: Into<Vec<u8>>>(self, body: B, mime: Mime) -> Self {
+        self.body(body).header("Content-Type", &mime.to_string())
+    }
+
+    /// Materializes the method, URL, headers and body the request would be
+    /// sent with, without sending it.
+    ///
+    /// See [`PreparedRequest`](struct.PreparedRequest.html) for more information.
+    pub fn build(&self) -> PreparedRequest {
+        let Assembled { body, cookies, headers: explicit, url } = self.assemble(self.body.clone());
+
+        // cURL leaves out its own value when a header with the same name is set
+        let mut headers = Vec::new();
+        let is_set = |name: &str| explicit.iter().any(|(key, _)| key.eq_ignore_ascii_case(name));
+        if !is_set("User-Agent") {
+            headers.push(("User-Agent".to_owned(), self.user_agent.clone()));
+        }
+        if let Some(ref referer) = self.referer {
+            if !is_set("Referer") {
+                headers.push(("Referer".to_owned(), referer.clone()));
+            }
+        }
+        headers.extend(explicit);
+        if !cookies.is_empty() {
+            headers.push(("Cookie".to_owned(), cookies));
+        }
+
+        PreparedRequest {
+            body,
+            headers,
+            method: self.method.clone(),
+            url
+        }
+    }
+
+    /// Verifies the certificate of the server against the CA certificates in
+    /// the given PEM file instead of the system trust store.
+    ///
+    /// This replaces any CA certificates previously set through
+    /// [`ca_cert_bytes`](#method.ca_cert_bytes).
+    pub fn ca_cert(mut self, path: &Path) -> Self {
+        self.ca_cert = Some(path.to_owned());
+        self.ca_cert_bytes = None;
+        self
+    }
+
+    /// Verifies the certificate of the server against the given PEM-encoded
+    /// CA certificates instead of the system trust store.
+    ///
+    /// This replaces any CA certificates previously set through
+    /// [`ca_cert`](#method.ca_cert).
+    pub fn ca_cert_bytes(mut self, pem: &[u8]) -> Self {
+        self.ca_cert = None;
+        self.ca_cert_bytes = Some(pem.to_owned());
+        self
+    }
+
+    /// Restricts the cipher suites used for TLS 1.2 and older to the given list.
+    ///
+    /// The list uses the format of the TLS library cURL is built with, which
+    /// for OpenSSL is a colon-separated list like `ECDHE-RSA-AES128-GCM-SHA256:ECDHE-RSA-AES256-GCM-SHA384`.
+    /// If none of the ciphers is known, the request fails with a
+    /// [`RequestError::Curl`](enum.RequestError.html#variant.Curl). See
+    /// [`tls13_ciphers`](#method.tls13_ciphers) for the TLS 1.3 cipher suites.
+    /// If the list is empty, the request fails with
+    /// [`RequestError::EmptyCipherList`](enum.RequestError.html#variant.EmptyCipherList).
+    pub fn ciphers(mut self, list: &str) -> Self {
+        self.ciphers = Some(list.trim().to_owned());
+        self
+    }
+
+    /// Authenticates against the server using the given TLS client certificate.
+    ///
+    /// `key` is the file containing the private key, which may be omitted if
+    /// it is contained in the certificate file, as it's the case for PKCS#12
+    /// archives. `password` is used to decrypt the key or the archive.
+    ///
+    /// The files are expected to be PEM-encoded unless specified otherwise
+    /// through [`client_cert_type`](#method.client_cert_type).
+    pub fn client_cert(mut self, cert: &Path, key: Option<&Path>, password: Option<&str>) -> Self {
+        self.client_cert = Some((cert.to_owned(), key.map(|key| key.to_owned()), password.map(|p| p.to_owned())));
+        self
+    }
+
+    /// Sets the format of the TLS client certificate and key.
+    ///
+    /// Defaults to [`CertificateType::Pem`](enum.CertificateType.html#variant.Pem).
+    pub fn client_cert_type(mut self, cert_type: CertificateType) -> Self {
+        self.client_cert_type = cert_type;
+        self
+    }
+
+    /// Compresses the request body using the given encoding and sets the
+    /// `Content-Encoding` header accordingly.
+    ///
+    /// The compression takes place right before the request is sent, so it
+    /// applies to the final body regardless of whether it has been set before
+    /// or after calling this method. It does not apply to
+    /// [`multipart`](#method.multipart) bodies.
+    pub fn compress_body(mut self, encoding: Encoding) -> Self {
+        self.body_encoding = Some(encoding);
+        self
+    }
+
+    /// Calls the given function with the cURL handle to set options which are not
+    /// covered by this library.
+    ///
+    /// The function is called right before the transfer starts, after all options of
+    /// the request have been configured, so it may override them. Multiple functions
+    /// are called in the order they were added. An error returned by a function aborts
+    /// the request with [`RequestError::Curl`](enum.RequestError.html#variant.Curl).
+    /// Since retried requests configure a fresh handle, the function may be called
+    /// multiple times.
+    pub fn configure<F: Fn(&mut Easy) -> Result<(), CurlError> + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.configure.push(Arc::new(f));
+        self
+    }
+
+    /// Set the maximum time connecting to the server is allowed to take.
+    ///
+    /// This only limits the connection phase, the transfer itself may take
+    /// longer. It can be combined with [`timeout`](#method.timeout), in which
+    /// case whichever elapses first aborts the request. Both resolve to
+    /// [`RequestError::Timeout`](enum.RequestError.html#variant.Timeout).
+    ///
+    /// Defaults to cURL's built-in connect timeout of 300 seconds.
+    pub fn connect_timeout(mut self, duration: Duration) -> Self {
+        self.connect_timeout = Some(duration);
+        self
+    }
+
+    /// Connects to `to_host` and `to_port` whenever the request would connect to
+    /// `host` and `port`, e.g. to reach a specific backend behind a load balancer.
+    ///
+    /// Unlike [`resolve`](#method.resolve), this reroutes by host name and leaves
+    /// the URL, and thus the `Host` header and TLS server name, untouched. Adding
+    /// another route for the same host and port replaces the previous one.
+    pub fn connect_to(mut self, host: &str, port: u16, to_host: &str, to_port: u16) -> Self {
+        let prefix = format!("{}:{}:", bracket_ipv6(host), port);
+        let entry = format!("{}{}:{}", prefix, bracket_ipv6(to_host), to_port);
+        match self.connect_to.iter_mut().find(|entry| entry.starts_with(&prefix)) {
+            Some(existing) => *existing = entry,
+            None => self.connect_to.push(entry)
+        }
+        self
+    }
+
+    /// Declares the length of a body streamed from a reader, which is sent as
+    /// `Content-Length` instead of using chunked transfer encoding.
+    ///
+    /// The reader must yield exactly this many bytes. Bodies set in any other way
+    /// always declare their actual length, so this has no effect on them. See
+    /// [`body_reader`](#method.body_reader) for more information.
+    pub fn content_length(mut self, len: u64) -> Self {
+        self.content_length = Some(len);
+        self
+    }
+
+    /// Adds a cookie to the request.
+    ///
+    /// Cookies set this way are sent in addition to those from the
+    /// [`cookie_jar`](#method.cookie_jar).
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.cookies.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Adds all given cookies to the request.
+    ///
+    /// See [`Request::cookie`](#method.cookie) for more information.
+    pub fn cookies<I, K, V>(mut self, cookies: I) -> Self
+            where I: IntoIterator<Item = (K, V)>,
+                  K: AsRef<str>,
+                  V: AsRef<str> {
+        for (name, value) in cookies {
+            self = self.cookie(name.as_ref(), value.as_ref());
+        }
+        self
+    }
+
+    /// Uses the given `CookieJar` to attach cookies to the request and
+    /// to store the cookies set by the server in the response.
+    ///
+    /// Cookies set by [followed redirects](#method.follow_redirects) are
+    /// stored as well.
+    pub fn cookie_jar(mut self, jar: CookieJar) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Sets the option whether to accept invalid TLS certificates, e.g.
+    /// self-signed ones or ones issued for a different host.
+    ///
+    /// This disables all protection TLS offers against man-in-the-middle
+    /// attacks. Only ever use this for testing, never in production.
+    ///
+    /// Defaults to `false`.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Calls the given function with cURL's verbose debug output instead of
+    /// printing it to stderr.
+    ///
+    /// The function receives the kind of data, e.g. the headers sent to or
+    /// received from the server, and the raw data itself. This enables
+    /// [verbose output](#method.verbose) for the request.
+    pub fn debug_callback<F: Fn(DebugType, &[u8]) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.debug_callback = Some(Arc::new(f));
         self
     }
 
+    /// Authenticates the request using HTTP digest authentication.
+    ///
+    /// cURL answers the challenge of the server, so this costs an additional
+    /// round trip.
+    pub fn digest_auth(mut self, username: &str, password: &str) -> Self {
+        self.auth = Some((AuthScheme::Digest, username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// Sets how many seconds resolved host names are cached for.
+    ///
+    /// The cache is shared by all requests sent through the same `Session`. A
+    /// negative value caches them forever, `0` disables caching. Defaults to
+    /// cURL's built-in timeout of 60 seconds.
+    pub fn dns_cache_timeout(mut self, secs: i64) -> Self {
+        self.dns_cache_timeout = Some(secs);
+        self
+    }
+
+    /// Resolves host names using the given DNS servers instead of the ones
+    /// configured in the system.
+    ///
+    /// This requires a cURL built with c-ares, otherwise the request fails with
+    /// a [`RequestError::Curl`](enum.RequestError.html#variant.Curl) error
+    /// before anything is sent.
+    pub fn dns_servers(mut self, servers: &[SocketAddr]) -> Self {
+        let servers = servers.iter().map(|server| server.to_string()).collect::<Vec<_>>();
+        self.dns_servers = Some(servers.join(","));
+        self
+    }
+
+    /// Sends an empty request body along with `Content-Length: 0`.
+    ///
+    /// This replaces any previously set body. POST, PUT and PATCH requests
+    /// without a body are sent like this anyway, so this only makes the
+    /// intent explicit for them.
+    pub fn empty_body(self) -> Self {
+        self.body(Vec::new())
+    }
+
+    /// Sets the characters that are percent-encoded in the names and values of
+    /// the URL parameters.
+    ///
+    /// Defaults to [`EncodingSet::Strict`](enum.EncodingSet.html#variant.Strict).
+    /// The query string already contained in the request URL is left as it is.
+    pub fn encoding_set(mut self, set: EncodingSet) -> Self {
+        self.encoding_set = set;
+        self
+    }
+
+    /// Sets the option whether cURL may send `Expect: 100-continue` and wait for
+    /// the server's approval before sending larger request bodies.
+    ///
+    /// Some servers never answer the expectation, which stalls the request for a
+    /// second. Disabling it sends an empty `Expect` header suppressing it. Defaults
+    /// to `true`.
+    pub fn expect_continue(mut self, enabled: bool) -> Self {
+        self.headers.retain(|(name, value)| !(name.eq_ignore_ascii_case("Expect") && value.trim().is_empty()));
+        if enabled {
+            self
+        } else {
+            self.header("Expect", "")
+        }
+    }
+
     /// Sets the option whether to follow 3xx-redirects or not.
     ///
     /// Defaults to `true`.
@@ -91,38 +636,166 @@ impl Request {
         self
     }
 
-    /// Adds an HTTP header to the request.
-    pub fn header(mut self, name: &str, value: &str) -> Self {
-        self.headers.push((name.to_owned(), value.to_owned()));
+    /// Encodes the given pairs as `application/x-www-form-urlencoded` and uses
+    /// that as the request body. Also automatically sets the `Content-Type`
+    /// accordingly.
+    ///
+    /// This does not affect the URL parameters set via [`param`](#method.param).
+    pub fn form<I, K, V>(mut self, pairs: I) -> Self
+            where I: IntoIterator<Item = (K, V)>,
+                  K: AsRef<str>,
+                  V: AsRef<str> {
+        let body = Serializer::new(String::new()).extend_pairs(pairs).finish();
+        self.body = Some(body.into_bytes());
+        self.body_error = None;
+        self.body_reader = None;
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+    }
+
+    /// Serializes the given struct or map as `application/x-www-form-urlencoded`
+    /// through the `serde_urlencoded` crate and uses that as the request body.
+    /// Also automatically sets the `Content-Type` accordingly.
+    ///
+    /// `None` values are skipped. See [`Request::form`](#method.form) for ad-hoc
+    /// pairs and [`Request::query`](#method.query) for more information.
+    ///
+    /// If serialization fails, e.g. because the value contains nested structures,
+    /// sending the request fails with [`RequestError::Encode`](enum.RequestError.html#variant.Encode)
+    /// without contacting the server.
+    #[cfg(feature = "serde-serialization")]
+    pub fn form_struct<T: serde::Serialize>(mut self, value: &T) -> Self {
+        match serde_urlencoded::to_string(value) {
+            Ok(body) => {
+                self.body = Some(body.into_bytes());
+                self.body_error = None;
+                self.body_reader = None;
+            },
+            Err(err) => self.body_error = Some(Box::new(err))
+        }
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+    }
+
+    /// Sets an HTTP header of the request.
+    ///
+    /// This replaces all headers with the same name set before, names are
+    /// matched case-insensitively. See [`Request::append_header`](#method.append_header)
+    /// to send multiple values.
+    pub fn header(self, name: &str, value: &str) -> Self {
+        self.remove_header(name).append_header(name, value)
+    }
+
+    /// Adds all given request headers.
+    ///
+    /// The headers are appended in order after the ones set before, repeated
+    /// names are preserved. See [`Request::append_header`](#method.append_header)
+    /// for more information.
+    pub fn headers<I, K, V>(mut self, headers: I) -> Self
+            where I: IntoIterator<Item = (K, V)>,
+                  K: AsRef<str>,
+                  V: AsRef<str> {
+        for (name, value) in headers {
+            self = self.append_header(name.as_ref(), value.as_ref());
+        }
+        self
+    }
+
+    /// Sets the `Host` header independently of the host in the URL.
+    ///
+    /// This is useful to reach a virtual host at another address, e.g. together
+    /// with [`connect_to`](#method.connect_to) or [`resolve`](#method.resolve).
+    /// The host may be followed by a port, like `example.com:8080`, and IPv6
+    /// addresses must be enclosed in brackets.
+    ///
+    /// If the value is not a valid host, the header is not set and sending the
+    /// request fails with [`RequestError::InvalidHost`](enum.RequestError.html#variant.InvalidHost).
+    pub fn host(mut self, host: &str) -> Self {
+        if is_valid_host(host) {
+            self.invalid_host = None;
+            self.header("Host", host)
+        } else {
+            self.invalid_host = Some(host.to_owned());
+            self
+        }
+    }
+
+    /// Sets the HTTP version to use.
+    ///
+    /// By default, cURL uses HTTP/2 for HTTPS if the server supports it and
+    /// HTTP/1.1 otherwise. The version actually used is available through
+    /// [`Response::http_version`](struct.Response.html#method.http_version).
+    pub fn http_version(mut self, version: HttpVersion) -> Self {
+        self.http_version = Some(version);
+        self
+    }
+
+    /// Only requests the resource if it has been modified after the given point
+    /// in time by setting the `If-Modified-Since` header.
+    ///
+    /// If it hasn't, the server answers with `304 Not Modified`, see
+    /// [`Response::is_not_modified`](struct.Response.html#method.is_not_modified).
+    pub fn if_modified_since(self, time: SystemTime) -> Self {
+        self.header("If-Modified-Since", &fmt_http_date(time))
+    }
+
+    /// Only requests the resource if its entity tag differs from the given one
+    /// by setting the `If-None-Match` header.
+    ///
+    /// The entity tag is quoted unless it already is (or is a weak tag or `*`).
+    /// If it matches, the server answers with `304 Not Modified`, see
+    /// [`Response::is_not_modified`](struct.Response.html#method.is_not_modified).
+    pub fn if_none_match(self, etag: &str) -> Self {
+        let etag = if etag == "*" || etag.starts_with("W/") || (etag.len() > 1 && etag.starts_with('"') && etag.ends_with('"')) {
+            etag.to_owned()
+        } else {
+            format!("\"{}\"", etag)
+        };
+        self.header("If-None-Match", &etag)
+    }
+
+    /// Sends the request from the given network interface, which may be an
+    /// interface name, a host name or an IP address.
+    ///
+    /// Interface names are only supported on Unix-like systems, and binding to
+    /// an interface by name may require elevated privileges on Linux. Prefix the
+    /// value with `if!` or `host!` to force it to be used as interface or host name.
+    pub fn interface(mut self, interface: &str) -> Self {
+        self.interface = Some(interface.to_owned());
         self
     }
 
-    /// Sets the given request headers.
+    /// Restricts the IP versions host names are resolved to, e.g. to avoid
+    /// broken IPv6 routes on dual-stack hosts.
     ///
-    /// This overwrites all previously set headers.
-    pub fn headers(mut self, headers: Vec<(String, String)>) -> Self {
-        self.headers = headers;
+    /// Defaults to [`IpResolve::Any`](enum.IpResolve.html#variant.Any). The
+    /// address actually connected to is available through
+    /// [`Response::primary_ip`](struct.Response.html#method.primary_ip).
+    pub fn ip_resolve(mut self, pref: IpResolve) -> Self {
+        self.ip_resolve = pref;
         self
     }
 
     /// Serializes the given object to JSON and uses that as the request body.
-    /// Also automatically sets the `Content-Type` to `application/json`.
+    /// Also automatically sets the `Content-Type` to `application/json`, see
+    /// [`accept_json`](#method.accept_json) to ask for a JSON response as well.
     ///
-    /// ## Panics
-    /// Panics if serialization is not successful.
-    #[cfg(feature = "rustc-serialization")]
+    /// If serialization fails, sending the request fails with
+    /// [`RequestError::Encode`](enum.RequestError.html#variant.Encode) without
+    /// contacting the server.
+    #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
     pub fn json<T: rustc_serialize::Encodable>(self, body: &T) -> Self {
-        self.set_json(rustc_serialize::json::encode(body).unwrap().into_bytes())
+        self.set_json(rustc_serialize::json::encode(body).map(String::into_bytes).map_err(Into::into))
     }
 
     /// Serializes the given object to JSON and uses that as the request body.
-    /// Also automatically sets the `Content-Type` to `application/json`.
+    /// Also automatically sets the `Content-Type` to `application/json`, see
+    /// [`accept_json`](#method.accept_json) to ask for a JSON response as well.
     ///
-    /// ## Panics
-    /// Panics if serialization is not successful.
+    /// If serialization fails, sending the request fails with
+    /// [`RequestError::Encode`](enum.RequestError.html#variant.Encode) without
+    /// contacting the server.
     #[cfg(feature = "serde-serialization")]
     pub fn json<T: serde::Serialize>(self, body: &T) -> Self {
-        self.set_json(serde_json::to_vec(body).unwrap())
+        self.set_json(serde_json::to_vec(body).map_err(Into::into))
     }
 
     /// Sets the thresholds which, when reached, aborts a download due to too
@@ -136,7 +809,8 @@ impl Request {
     /// speed is too low.
     ///
     /// The values here default to [`LOW_SPEED_LIMIT`](constant.LOW_SPEED_LIMIT.html) and
-    /// [`LOW_SPEED_TIME`](constant.LOW_SPEED_TIME.html).
+    /// [`LOW_SPEED_TIME`](constant.LOW_SPEED_TIME.html). Aborted transfers resolve to
+    /// [`RequestError::LowSpeed`](enum.RequestError.html#variant.LowSpeed).
     pub fn lowspeed_limit(mut self, bytes: u32, per_duration: Duration) -> Self {
         self.lowspeed_limits = if bytes > 0 && per_duration > Duration::from_secs(0) {
             Some((bytes, per_duration))
@@ -146,76 +820,547 @@ impl Request {
         self
     }
 
+    /// Sets the maximum size of the response body in bytes.
+    ///
+    /// The transfer is aborted as soon as the received body exceeds the limit
+    /// and the future resolves to
+    /// [`RequestError::BodyTooLarge`](enum.RequestError.html#variant.BodyTooLarge).
+    /// Unlimited by default.
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Limits the download speed to the given amount of bytes per second.
+    ///
+    /// Pass 0 to disable the limit, which is the default.
+    pub fn max_recv_speed(mut self, bytes_per_sec: u64) -> Self {
+        self.max_recv_speed = bytes_per_sec;
+        self
+    }
+
     /// Sets the maximum amount of redirects cURL will follow when
     /// [`Request::follow_redirects`](#method.follow_redirects) is
     /// enabled.
+    ///
+    /// If the server redirects more often, the request fails with
+    /// [`RequestError::TooManyRedirects`](enum.RequestError.html#variant.TooManyRedirects)
+    /// instead of resolving to the last 3xx-response.
+    ///
+    /// Defaults to [`MAX_REDIRECTS`](constant.MAX_REDIRECTS.html).
     pub fn max_redirects(mut self, max_redirects: u32) -> Self {
         self.max_redirects = max_redirects;
         self
     }
 
+    /// Limits the upload speed to the given amount of bytes per second.
+    ///
+    /// Pass 0 to disable the limit, which is the default.
+    pub fn max_send_speed(mut self, bytes_per_sec: u64) -> Self {
+        self.max_send_speed = bytes_per_sec;
+        self
+    }
+
+    /// Sets the newest TLS version to use.
+    ///
+    /// By default, the newest version both cURL and the server support is used.
+    pub fn max_tls_version(mut self, version: TlsVersion) -> Self {
+        self.max_tls_version = Some(version);
+        self
+    }
+
+    /// Sets the oldest TLS version to accept.
+    ///
+    /// If the server doesn't support this or any newer version, the request
+    /// fails with a [`RequestError::Curl`](enum.RequestError.html#variant.Curl)
+    /// before anything is sent. By default, the oldest version cURL
+    /// considers secure is accepted.
+    pub fn min_tls_version(mut self, version: TlsVersion) -> Self {
+        self.min_tls_version = Some(version);
+        self
+    }
+
+    /// Uses the given `multipart/form-data` form as the request body.
+    ///
+    /// The `Content-Type` including the generated boundary is set
+    /// automatically. This replaces any previously set body.
+    pub fn multipart(mut self, form: Multipart) -> Self {
+        self.body = None;
+        self.body_error = None;
+        self.body_reader = None;
+        self.multipart = Some(form);
+        self
+    }
+
+    /// Sets the hosts which should be reached directly instead of through
+    /// the configured proxy.
+    ///
+    /// `hosts` is a comma-separated list in the format of the `NO_PROXY`
+    /// environment variable: every entry matches the host itself as well as
+    /// all of its subdomains, a leading dot is optional and `*` matches all
+    /// hosts.
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy = Some(hosts.to_owned());
+        self
+    }
+
+    /// Calls the given function with the progress of the transfer.
+    ///
+    /// The arguments are the total and the current amount of bytes to download,
+    /// followed by the total and the current amount of bytes to upload. Totals
+    /// are 0 while unknown. The function is called frequently on the event loop,
+    /// so it must return quickly.
+    pub fn on_progress<F: Fn(u64, u64, u64, u64) + Send + Sync + 'static>(mut self, f: F) -> Self {
+        self.on_progress = Some(Arc::new(f));
+        self
+    }
+
     /// Adds a URL parameter to the request.
+    ///
+    /// Both name and value are percent-encoded. The parameters are appended to
+    /// the query string already contained in the request URL, and adding a
+    /// parameter with the same name multiple times sends all of the values.
     pub fn param(mut self, name: &str, value: &str) -> Self {
         self.params.push((name.to_owned(), value.to_owned()));
         self
     }
 
-    /// Sets the given request URL parameters.
+    /// Adds all given URL parameters to the request.
     ///
-    /// This overwrites all previously set parameters.
-    pub fn params(mut self, params: Vec<(String, String)>) -> Self {
-        self.params = params;
+    /// The parameters are appended in order, repeated names are preserved.
+    /// See [`Request::param`](#method.param) for more information.
+    pub fn params<I, K, V>(mut self, params: I) -> Self
+            where I: IntoIterator<Item = (K, V)>,
+                  K: AsRef<str>,
+                  V: AsRef<str> {
+        for (name, value) in params {
+            self = self.param(name.as_ref(), value.as_ref());
+        }
         self
     }
 
-    /// Creates a new `Session` on the specified event loop to send the HTTP request through
-    /// and returns a future that fires off the request, parses the response and resolves to
-    /// a `Response`-struct on success.
+    /// Only accepts servers whose certificate contains the public key with the
+    /// given base64-encoded SHA-256 hash.
     ///
-    /// ## Panics
-    /// Panics in case of native exceptions in cURL.
-    pub fn send(self, h: Handle) -> BoxFuture<Response, Error> {
-        self.send_with_session(&Session::new(h))
+    /// The hash may be given with or without the `sha256//` prefix. Pinning
+    /// several keys accepts any of them, e.g. to roll over to a new key. If the
+    /// key of the server doesn't match, the request fails with a
+    /// [`RequestError::Curl`](enum.RequestError.html#variant.Curl) before
+    /// anything is sent. This is checked in addition to the regular verification
+    /// of the certificate.
+    pub fn pin_public_key(mut self, sha256_base64: &str) -> Self {
+        let hash = sha256_base64.trim();
+        let hash = hash.strip_prefix("sha256//").unwrap_or(hash);
+        self.pinned_public_keys.push(format!("sha256//{}", hash));
+        self
     }
 
-    /// Uses the given `Session` to send the HTTP request through and returns a future that
-    /// fires off the request, parses the response and resolves to a `Response`-struct on success.
+    /// Sends the request through the given proxy.
     ///
-    /// ## Panics
-    /// Panics in case of native exceptions in cURL.
-    pub fn send_with_session(mut self, session: &Session) -> BoxFuture<Response, Error> {
-        {
-            let mut query_pairs = self.url.query_pairs_mut();
-            for (key, value) in self.params {
-                query_pairs.append_pair(key.trim(), value.trim());
-            }
-        }
-        let headers = {
-            let mut list = List::new();
-            for (key, value) in self.headers {
-                list.append(&format!("{}: {}", key.trim(), value.trim())).expect("Failed to append header value to (native cURL) header list.");
-            }
-            list
-        };
+    /// `http://`, `https://` and `socks5://` proxies are supported. Credentials
+    /// contained in the URL are used to authenticate against the proxy.
+    ///
+    /// `https://` requests through an HTTP(S) proxy are tunneled with `CONNECT`,
+    /// so the proxy never sees the decrypted traffic.
+    pub fn proxy(mut self, proxy: &Url) -> Self {
+        self.proxy = Some(proxy.clone());
+        self
+    }
 
-        let mut easy = self.handle.unwrap_or_else(|| Easy::new());
-        let (header_tx, header_rx) = channel();
-        let (body_tx, body_rx) = channel();
+    /// Authenticates against the proxy with HTTP basic authentication.
+    ///
+    /// These credentials take precedence over the ones contained in the proxy URL.
+    pub fn proxy_basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.proxy_credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
 
-        let config_res = {
-            // Make the borrow checker happy
-            let body = self.body;
-            let follow_redirects = self.follow_redirects;
-            let lowspeed_limits = self.lowspeed_limits;
-            let max_redirects = self.max_redirects;
+    /// Configures the proxy from the environment.
+    ///
+    /// Depending on the scheme of the request URL, this reads the proxy from
+    /// `HTTPS_PROXY` or `HTTP_PROXY`, and the hosts which should not be
+    /// proxied from `NO_PROXY`. The lowercase variants of the variables
+    /// take precedence. Unset or invalid variables are ignored.
+    pub fn proxy_from_env(mut self) -> Self {
+        fn var(name: &str) -> Option<String> {
+            env::var(name.to_lowercase())
+                .or_else(|_| env::var(name))
+                .ok()
+                .filter(|value| !value.is_empty())
+        }
+
+        let proxy_var = if self.url.scheme() == "https" { "HTTPS_PROXY" } else { "HTTP_PROXY" };
+        if let Some(proxy) = var(proxy_var).and_then(|proxy| Url::parse(&proxy).ok()) {
+            self.proxy = Some(proxy);
+        }
+        if let Some(no_proxy) = var("NO_PROXY") {
+            self.no_proxy = Some(no_proxy);
+        }
+        self
+    }
+
+    /// Serializes the given struct or map into URL parameters through the
+    /// `serde_urlencoded` crate and adds them to the request.
+    ///
+    /// The parameters are appended to the ones added before. `None` values are
+    /// skipped, see the `serde_urlencoded` crate for which values are supported.
+    /// See [`Request::param`](#method.param) for more information.
+    ///
+    /// If serialization fails, e.g. because the value contains nested structures,
+    /// sending the request fails with [`RequestError::Encode`](enum.RequestError.html#variant.Encode)
+    /// without contacting the server.
+    #[cfg(feature = "serde-serialization")]
+    pub fn query<T: serde::Serialize>(mut self, value: &T) -> Self {
+        match serde_urlencoded::to_string(value) {
+            Ok(query) => self.params.extend(form_urlencoded::parse(query.as_bytes()).into_owned()),
+            Err(err) => self.query_error = Some(Box::new(err))
+        }
+        self
+    }
+
+    /// Removes all headers with the given name set before.
+    ///
+    /// Names are matched case-insensitively. This also removes headers set by
+    /// methods like [`json`](#method.json), but not the ones cURL adds itself
+    /// like `Host`, see [`expect_continue`](#method.expect_continue) for an example
+    /// on how to suppress those.
+    pub fn remove_header(mut self, name: &str) -> Self {
+        self.headers.retain(|(key, _)| !key.trim().eq_ignore_ascii_case(name.trim()));
+        self
+    }
+
+    /// Only requests the given range of bytes of the resource, starting at the
+    /// byte `start` and ending at the byte `end` inclusively or at the end of
+    /// the resource if `end` is `None`.
+    ///
+    /// Servers supporting ranges answer with `206 Partial Content`.
+    pub fn range(mut self, start: u64, end: Option<u64>) -> Self {
+        self.range = Some(match end {
+            Some(end) => format!("{}-{}", start, end),
+            None => format!("{}-", start)
+        });
+        self
+    }
+
+    /// Sets the `Referer` of the request.
+    ///
+    /// A `Referer` set through [`header`](#method.header) takes precedence.
+    pub fn referer(mut self, url: &str) -> Self {
+        self.referer = Some(url.to_owned());
+        self
+    }
+
+    /// Resolves the given host and port to the given address instead of
+    /// querying DNS.
+    ///
+    /// Adding multiple addresses for the same host and port makes cURL try
+    /// them in order.
+    pub fn resolve(mut self, host: &str, port: u16, addr: IpAddr) -> Self {
+        let prefix = format!("{}:{}:", host, port);
+        let addr = match addr {
+            IpAddr::V4(addr) => addr.to_string(),
+            IpAddr::V6(addr) => format!("[{}]", addr)
+        };
+        match self.resolve.iter_mut().find(|entry| entry.starts_with(&prefix)) {
+            Some(entry) => {
+                entry.push(',');
+                entry.push_str(&addr);
+            },
+            None => self.resolve.push(prefix + &addr)
+        }
+        self
+    }
+
+    /// Re-issues the request according to the given policy if it fails.
+    ///
+    /// The delays between the attempts are driven by the event loop, so they
+    /// don't block it. Since this requires access to the event loop, retries
+    /// are only performed by [`send`](#method.send) and
+    /// [`Client::send`](struct.Client.html#method.send), but not by
+    /// [`send_with_session`](#method.send_with_session). Once all attempts are
+    /// used up, the future resolves to the result of the last one.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Creates a new `Session` on the specified event loop to send the HTTP request through
+    /// and returns a future that fires off the request, parses the response and resolves to
+    /// a `Response`-struct on success.
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send(self, h: Handle) -> ResponseFuture {
+        let session = Session::new(h.clone());
+        self.send_retrying(&session, &h)
+    }
+
+    /// Creates a new `Session` on the specified event loop to send the HTTP request through
+    /// and returns a future that fires off the request together with a handle to abort it.
+    ///
+    /// Cancelling drops the transfer, which closes its connection and frees its resources,
+    /// and resolves the future to [`RequestError::Cancelled`](enum.RequestError.html#variant.Cancelled).
+    /// Pending [retries](#method.retry) are cancelled as well.
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send_cancelable(self, h: Handle) -> (ResponseFuture, CancelHandle) {
+        let (tx, rx) = oneshot::channel();
+        let cancelled = rx.then(|result| match result {
+            Ok(()) => Either::A(future::err(RequestError::Cancelled)),
+            // The handle was dropped without cancelling the request
+            Err(_) => Either::B(future::empty())
+        });
+        let future = self.send(h)
+            .select(cancelled)
+            .map(|(response, _)| response)
+            .map_err(|(err, _)| err);
+        (Box::new(future), CancelHandle { tx })
+    }
+
+    /// Sends the request on a new, single-use event loop and returns the response.
+    ///
+    /// This blocks the current thread until the request has completed, so it is only
+    /// meant for scripts and tests. Never call it from within a running event loop.
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send_default(self) -> Result<Response, RequestError> {
+        let mut evloop = Core::new()?;
+        let request = self.send(evloop.handle());
+        evloop.run(request)
+    }
+
+    /// Creates a new `Session` on the specified event loop to send the HTTP request through
+    /// and returns a future that fires off the request and resolves to the head of the
+    /// response and a stream of its body as soon as the body starts to arrive.
+    ///
+    /// The transfer is driven by the event loop in the background and yields the body
+    /// in chunks as cURL receives them, so large responses like JSON lines can be
+    /// processed incrementally, see [`BodyStream::json_lines`](struct.BodyStream.html#method.json_lines).
+    /// Chunks are buffered until the stream is polled, and dropping the stream aborts
+    /// the transfer. The request is never
+    /// [retried](#method.retry). If the transfer fails before the head has arrived, the
+    /// future fails, afterwards the stream fails.
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send_streaming(self, h: Handle) -> StreamingFuture {
+        let (head_tx, head_rx) = oneshot::channel();
+        let (chunk_tx, chunk_rx) = mpsc::unbounded();
+        let sink = Arc::new(Mutex::new(ChunkWriter::new(chunk_tx.clone())));
+        let transfer = self.send_with_sink(&Session::new(h.clone()), Some(sink), Some(head_tx));
+        h.spawn(transfer.then(move |result| {
+            if let Err(err) = result {
+                let _ = chunk_tx.unbounded_send(Err(err));
+            }
+            Ok(())
+        }));
+
+        Box::new(head_rx.then(move |head| match head {
+            Ok(head) => Either::A(future::ok((head, BodyStream::new(chunk_rx)))),
+            // The transfer failed before the head arrived and left the reason in the stream
+            Err(_) => Either::B(BodyStream::new(chunk_rx)
+                .into_future()
+                .map_err(|(err, _)| err)
+                .and_then(|_| Err(io::Error::other("The transfer was aborted.").into())))
+        }))
+    }
+
+    /// Creates a new `Session` on the specified event loop to send the HTTP request through
+    /// and returns a future that fires off the request and writes the response body to the
+    /// given sink as it arrives.
+    ///
+    /// The `Response`-struct the future resolves to contains the status code and headers,
+    /// but an empty body. Since the body cannot be replayed into the sink, the request is
+    /// never [retried](#method.retry). Failing to write to the sink aborts the transfer and
+    /// resolves the future to [`RequestError::Io`](enum.RequestError.html#variant.Io).
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send_to<W: Write + Send + 'static>(self, h: Handle, sink: W) -> ResponseFuture {
+        self.send_with_sink(&Session::new(h), Some(Arc::new(Mutex::new(sink))), None)
+    }
+
+    /// Uses the given `Session` to send the HTTP request through and returns a future that
+    /// fires off the request, parses the response and resolves to a `Response`-struct on success.
+    ///
+    /// ## Panics
+    /// Panics in case of native exceptions in cURL.
+    pub fn send_with_session(self, session: &Session) -> ResponseFuture {
+        self.send_with_sink(session, None, None)
+    }
+
+    /// Sends the request through the given session, either collecting the response body
+    /// or writing it to the given sink.
+    ///
+    /// The head of the response is sent to `head_tx` as soon as the body starts to
+    /// arrive, or when the transfer completes if there is no body.
+    fn send_with_sink(mut self,
+                      session: &Session,
+                      sink: Option<Arc<Mutex<dyn Write + Send>>>,
+                      head_tx: Option<oneshot::Sender<ResponseHead>>) -> ResponseFuture {
+        if let Some(err) = self.query_error.take().or_else(|| self.body_error.take()) {
+            return Box::new(failed(RequestError::Encode(err)));
+        }
+        if let Some(host) = self.invalid_host.take() {
+            return Box::new(failed(RequestError::InvalidHost(host)));
+        }
+        if self.ciphers.iter().chain(&self.tls13_ciphers).any(|list| list.is_empty()) {
+            return Box::new(failed(RequestError::EmptyCipherList));
+        }
+        let body = self.body.take();
+        let Assembled { body, cookies, headers, url } = self.assemble(body);
+        let request_method = self.method.clone();
+        let request_url = url.clone();
+        let headers = {
+            let mut list = List::new();
+            for (key, value) in headers {
+                list.append(&format!("{}: {}", key, value)).expect("Failed to append header value to (native cURL) header list.");
+            }
+            list
+        };
+
+        let cookie_jar = self.cookie_jar;
+        let mut easy = self.handle.unwrap_or_else(Easy::new);
+        let header_lines = Arc::new(Mutex::new(Vec::new()));
+        let head_tx = Arc::new(Mutex::new(head_tx));
+        let (body_tx, body_rx) = channel();
+        let body_too_large = Arc::new(AtomicBool::new(false));
+        let lowspeed_limited = self.lowspeed_limits.is_some();
+        let timeout = self.timeout;
+        let sink_error = Arc::new(Mutex::new(None::<io::Error>));
+        let upload_error = Arc::new(Mutex::new(None::<io::Error>));
+        let mut mime_post = None;
+
+        let config_res = {
+            // Make the borrow checker happy
+            let accept_encoding = match self.accept_encoding {
+                _ if !self.decompress => None,
+                Some(ref encodings) => Some(encodings.iter().map(|e| e.as_ref()).collect::<Vec<_>>().join(", ")),
+                None => Some(String::new()) // Empty string enables all supported encodings
+            };
+            let accept_invalid_certs = self.accept_invalid_certs;
+            let auth = self.auth;
+            let body_reader = self.body_reader;
+            let content_length = self.content_length;
+            let ca_cert = self.ca_cert;
+            let ca_cert_bytes = self.ca_cert_bytes;
+            let ciphers = self.ciphers;
+            let tls13_ciphers = self.tls13_ciphers;
+            let client_cert = self.client_cert;
+            let client_cert_type = self.client_cert_type;
+            let connect_timeout = self.connect_timeout;
+            let connect_to = to_list(&self.connect_to);
+            let follow_redirects = self.follow_redirects;
+            let http_version = self.http_version;
+            let interface = self.interface;
+            let ip_resolve = self.ip_resolve;
+            let lowspeed_limits = self.lowspeed_limits;
+            let max_body_size = self.max_body_size;
+            let max_recv_speed = self.max_recv_speed;
+            let max_redirects = self.max_redirects;
+            let max_send_speed = self.max_send_speed;
+            let max_tls_version = self.max_tls_version;
+            let min_tls_version = self.min_tls_version;
             let method = self.method;
-            let timeout = self.timeout;
-            let url = self.url;
-            let mut first_header = true;
+            let multipart = self.multipart;
+            let mime_post = &mut mime_post;
+            let no_proxy = self.no_proxy;
+            let on_progress = self.on_progress;
+            let pinned_public_keys = self.pinned_public_keys.join(";");
+            let proxy_credentials = self.proxy_credentials;
+            let configure = self.configure;
+            let debug_callback = self.debug_callback;
+            let dns_cache_timeout = self.dns_cache_timeout;
+            let dns_servers = self.dns_servers;
+            let verbose = self.verbose;
+            let unix_socket = self.unix_socket;
+            let user_agent = self.user_agent;
+            let range = self.range;
+            let referer = self.referer;
+            let resolve = to_list(&self.resolve);
+            let proxy = match (self.proxy, no_proxy.as_ref(), url.host_str()) {
+                (Some(_), Some(no_proxy), Some(host)) if bypasses_proxy(no_proxy, host) => None,
+                (proxy, _, _) => proxy
+            };
+            let mut body_size = 0;
+            let body_too_large = body_too_large.clone();
+            let head_lines = header_lines.clone();
+            let head_tx = head_tx.clone();
+            let header_lines = header_lines.clone();
+            let sink = sink.clone();
+            let sink_error = sink_error.clone();
 
             // We cannot use try! here, since we're dealing with futures, not with Results
             Ok(())
-                .and_then(|_| easy.accept_encoding(""))
+                .and_then(|_| if let Some(ref accept_encoding) = accept_encoding {
+                    easy.accept_encoding(accept_encoding)
+                } else {
+                    easy.http_content_decoding(false)
+                })
+                .and_then(|_| if let Some((scheme, ref username, ref password)) = auth {
+                    let mut auth = Auth::new();
+                    match scheme {
+                        AuthScheme::Basic => auth.basic(true),
+                        AuthScheme::Digest => auth.digest(true)
+                    };
+                    easy.http_auth(&auth)
+                        .and_then(|_| easy.username(username))
+                        .and_then(|_| easy.password(password))
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref path) = ca_cert {
+                    easy.cainfo(path)
+                } else if let Some(ref pem) = ca_cert_bytes {
+                    easy.ssl_cainfo_blob(pem)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref ciphers) = ciphers {
+                    easy.ssl_cipher_list(ciphers)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref ciphers) = tls13_ciphers {
+                    set_tls13_ciphers(&mut easy, ciphers)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some((ref cert, ref key, ref password)) = client_cert {
+                    easy.ssl_cert(cert)
+                        .and_then(|_| easy.ssl_cert_type(client_cert_type.as_ref()))
+                        .and_then(|_| if let Some(ref key) = *key {
+                            // PKCS#12 is no valid key type, the key always resides in the archive
+                            easy.ssl_key(key)
+                                .and_then(|_| if client_cert_type != CertificateType::P12 {
+                                    easy.ssl_key_type(client_cert_type.as_ref())
+                                } else {
+                                    Ok(())
+                                })
+                        } else {
+                            Ok(())
+                        })
+                        .and_then(|_| if let Some(ref password) = *password {
+                            easy.key_password(password)
+                        } else {
+                            Ok(())
+                        })
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(timeout) = connect_timeout {
+                    easy.connect_timeout(timeout)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| connect_to.and_then(|list| easy.connect_to(list)))
+                .and_then(|_| if !cookies.is_empty() {
+                    easy.cookie(&cookies)
+                } else {
+                    Ok(())
+                })
                 .and_then(|_| easy.custom_request(method.as_ref()))
                 .and_then(|_| if follow_redirects {
                     easy.follow_location(true)
@@ -226,17 +1371,97 @@ impl Request {
                 .and_then(|_| easy.header_function(move |header| {
                     match str::from_utf8(header) {
                         Ok(s) => {
-                            let s = s.trim(); // Headers are \n-separated
-                            if !first_header && s.len() > 0 { // First header is HTTP status line, don't want that
-                                let _ = header_tx.send(s.to_owned());
-                            }
-                            first_header = false;
+                            // Headers are \n-separated, the empty line terminating
+                            // a header block is kept to tell trailers apart
+                            header_lines.lock().unwrap().push(s.trim().to_owned());
                             true
                         },
                         Err(_) => false
                     }
                 }))
                 .and_then(|_| easy.http_headers(headers))
+                .and_then(|_| easy.max_recv_speed(max_recv_speed))
+                .and_then(|_| easy.max_send_speed(max_send_speed))
+                .and_then(|_| if let Some(ref no_proxy) = no_proxy {
+                    easy.noproxy(no_proxy)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(secs) = dns_cache_timeout {
+                    set_dns_cache_timeout(&mut easy, secs)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref servers) = dns_servers {
+                    easy.dns_servers(servers)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| easy.verbose(verbose || debug_callback.is_some()))
+                .and_then(|_| if let Some(debug_callback) = debug_callback {
+                    easy.debug_function(move |kind, data| debug_callback(kind.into(), data))
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(on_progress) = on_progress {
+                    easy.progress(true).and_then(|_| set_xferinfo(&mut easy, on_progress))
+                } else {
+                    easy.progress(false)
+                })
+                .and_then(|_| if !pinned_public_keys.is_empty() {
+                    easy.pinned_public_key(&pinned_public_keys)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref proxy) = proxy {
+                    let (username, password) = match proxy_credentials {
+                        Some((ref username, ref password)) => (username.clone(), password.clone()),
+                        None => (
+                            percent_decode(proxy.username().as_bytes()).decode_utf8_lossy().into_owned(),
+                            proxy.password()
+                                .map(|password| percent_decode(password.as_bytes()).decode_utf8_lossy().into_owned())
+                                .unwrap_or_default()
+                        )
+                    };
+                    let tunnel = url.scheme() == "https" && (proxy.scheme() == "http" || proxy.scheme() == "https");
+                    let mut address = proxy.clone();
+                    let _ = address.set_username("");
+                    let _ = address.set_password(None);
+
+                    easy.proxy(address.as_str())
+                        .and_then(|_| easy.http_proxy_tunnel(tunnel))
+                        .and_then(|_| if !username.is_empty() {
+                            let mut auth = Auth::new();
+                            auth.basic(true);
+                            easy.proxy_auth(&auth)
+                                .and_then(|_| easy.proxy_username(&username))
+                                .and_then(|_| easy.proxy_password(&password))
+                        } else {
+                            Ok(())
+                        })
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(version) = http_version {
+                    easy.http_version(match version {
+                        HttpVersion::Http10 => CurlHttpVersion::V10,
+                        HttpVersion::Http11 => CurlHttpVersion::V11,
+                        HttpVersion::Http2 => CurlHttpVersion::V2,
+                        HttpVersion::Http2PriorKnowledge => CurlHttpVersion::V2PriorKnowledge
+                    })
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref interface) = interface {
+                    easy.interface(interface)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| easy.ip_resolve(match ip_resolve {
+                    IpResolve::Any => CurlIpResolve::Any,
+                    IpResolve::V4 => CurlIpResolve::V4,
+                    IpResolve::V6 => CurlIpResolve::V6
+                }))
                 .and_then(|_| if let Some((bytes, per_time)) = lowspeed_limits {
                     easy.low_speed_limit(bytes)
                         .and_then(|_| easy.low_speed_time(per_time))
@@ -250,6 +1475,51 @@ impl Request {
                 })
                 .and_then(|_| if let Some(ref body) = body {
                     easy.post_fields_copy(body)
+                } else if let Some(ref multipart) = multipart {
+                    multipart.attach(&mut easy).map(|post| *mime_post = Some(post))
+                } else if let Some(reader) = body_reader {
+                    // Without a declared size cURL falls back to chunked transfer encoding
+                    let upload_error = upload_error.clone();
+                    easy.post(true)
+                        .and_then(|_| if let Some(len) = content_length {
+                            easy.post_field_size(len)
+                        } else {
+                            Ok(())
+                        })
+                        .and_then(|_| easy.read_function(move |buf| {
+                            reader.lock().unwrap().read(buf).map_err(|err| {
+                                *upload_error.lock().unwrap() = Some(err);
+                                ReadError::Abort
+                            })
+                        }))
+                } else if matches!(method, Method::Post | Method::Put | Method::Patch) {
+                    // Make cURL send `Content-Length: 0` instead of no body framing at all
+                    easy.post_fields_copy(&[])
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref range) = range {
+                    easy.range(range)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| if let Some(ref referer) = referer {
+                    easy.referer(referer)
+                } else {
+                    Ok(())
+                })
+                .and_then(|_| resolve.and_then(|list| easy.resolve(list)))
+                .and_then(|_| easy.ssl_verify_peer(!accept_invalid_certs))
+                .and_then(|_| easy.ssl_verify_host(!accept_invalid_certs))
+                .and_then(|_| if min_tls_version.is_some() || max_tls_version.is_some() {
+                    let version = |version| match version {
+                        Some(TlsVersion::Tls10) => SslVersion::Tlsv10,
+                        Some(TlsVersion::Tls11) => SslVersion::Tlsv11,
+                        Some(TlsVersion::Tls12) => SslVersion::Tlsv12,
+                        Some(TlsVersion::Tls13) => SslVersion::Tlsv13,
+                        None => SslVersion::Default
+                    };
+                    easy.ssl_min_max_version(version(min_tls_version), version(max_tls_version))
                 } else {
                     Ok(())
                 })
@@ -258,17 +1528,61 @@ impl Request {
                 } else {
                     Ok(())
                 })
+                .and_then(|_| if let Some(ref path) = unix_socket {
+                    easy.unix_socket_path(Some(path))
+                } else {
+                    Ok(())
+                })
                 .and_then(|_| easy.url(url.as_str()))
+                .and_then(|_| easy.useragent(&user_agent))
                 .and_then(|_| easy.write_function(move |data| {
-                    let _ = body_tx.send(Vec::from(data));
+                    body_size += data.len();
+                    if max_body_size.is_some_and(|max| body_size > max) {
+                        // Not consuming the data makes cURL abort the transfer
+                        body_too_large.store(true, Ordering::SeqCst);
+                        return Ok(0);
+                    }
+                    if let Some(head_tx) = head_tx.lock().unwrap().take() {
+                        let _ = head_tx.send(ResponseHead::from_lines(&head_lines.lock().unwrap()));
+                    }
+                    if let Some(ref sink) = sink {
+                        if let Err(err) = sink.lock().unwrap().write_all(data) {
+                            *sink_error.lock().unwrap() = Some(err);
+                            return Ok(0);
+                        }
+                    } else {
+                        let _ = body_tx.send(Vec::from(data));
+                    }
                     Ok(data.len())
                 }))
+                .and_then(|_| configure.iter().try_for_each(|f| f(&mut easy)))
         };
 
         match config_res {
-            Ok(_) => session.perform(easy)
-                            .map_err(|err| err.into_error())
-                            .map(move |ez| {
+            Ok(_) => Box::new(session.perform(easy)
+                            .map_err(move |mut err| if body_too_large.load(Ordering::SeqCst) {
+                                RequestError::BodyTooLarge
+                            } else if let Some(err) = sink_error.lock().unwrap().take() {
+                                RequestError::Io(err)
+                            } else if let Some(err) = upload_error.lock().unwrap().take() {
+                                RequestError::Io(err)
+                            } else {
+                                let easy = err.take_easy();
+                                match RequestError::from(err.into_error()) {
+                                    RequestError::Timeout if lowspeed_limited && easy.is_some_and(|easy| is_stalled(&easy, timeout)) => {
+                                        RequestError::LowSpeed
+                                    },
+                                    err => err
+                                }
+                            })
+                            .and_then(move |mut ez| {
+                                if let Some(post) = mime_post {
+                                    post.detach(&mut ez);
+                                }
+                                if let Some(sink) = sink {
+                                    sink.lock().unwrap().flush()?;
+                                }
+
                                 // In an ideal world where receiver_try_iter is stable
                                 // we could shorten this code to two lines.
                                 let body = {
@@ -278,29 +1592,95 @@ impl Request {
                                     }
                                     b
                                 };
-                                let headers = {
-                                    let mut h = Vec::new();
-                                    while let Ok(hdr) = header_rx.try_recv() {
-                                        h.push(hdr);
-                                    }
-                                    h
-                                };
+                                let headers = mem::take(&mut *header_lines.lock().unwrap());
+                                if let Some(head_tx) = head_tx.lock().unwrap().take() {
+                                    let _ = head_tx.send(ResponseHead::from_lines(&headers));
+                                }
 
-                                Response::new(ez, headers, body)
-                            })
-                            .boxed(),
-            Err(error) => failed(error.into()).boxed()
+                                // Redirects may set cookies as well, e.g. after a login
+                                if let Some(jar) = cookie_jar {
+                                    for (url, cookie) in hop_cookies(&headers, &request_url) {
+                                        jar.store(&url, cookie);
+                                    }
+                                }
+                                Ok(Response::new(ez, headers, body).with_request(request_method, request_url))
+                            })),
+            Err(error) => Box::new(failed(error.into()))
         }
     }
 
+    /// Sets the given string as request body and the `Content-Type`
+    /// to `text/plain; charset=utf-8`.
+    ///
+    /// This replaces any previously set body.
+    pub fn text(self, text: &str) -> Self {
+        let mime = Mime(TopLevel::Text, SubLevel::Plain, vec![(Attr::Charset, Value::Utf8)]);
+        self.body_with_type(text, mime)
+    }
+
+    /// Restricts the cipher suites used for TLS 1.3 to the given list.
+    ///
+    /// The list is colon-separated, like `TLS_AES_128_GCM_SHA256:TLS_AES_256_GCM_SHA384`.
+    /// See [`ciphers`](#method.ciphers) for older TLS versions. If the list is
+    /// empty, the request fails with
+    /// [`RequestError::EmptyCipherList`](enum.RequestError.html#variant.EmptyCipherList).
+    pub fn tls13_ciphers(mut self, list: &str) -> Self {
+        self.tls13_ciphers = Some(list.trim().to_owned());
+        self
+    }
+
     /// Set the maximum time the request is allowed to take.
     ///
-    /// Disabled by default in favor of [`lowspeed_limit`]
+    /// The timeout covers the entire transfer, including name resolution and
+    /// connecting to the server. When it elapses, the future returned by
+    /// [`send`](#method.send) resolves to
+    /// [`RequestError::Timeout`](enum.RequestError.html#variant.Timeout).
+    ///
+    /// Disabled by default in favor of [`lowspeed_limit`](#method.lowspeed_limit).
     pub fn timeout(mut self, duration: Duration) -> Self {
         self.timeout = Some(duration);
         self
     }
 
+    /// Renders the request as an equivalent `curl` command line, e.g. for bug reports.
+    ///
+    /// This reflects the method, URL parameters, headers, cookies, authentication,
+    /// proxy, Unix socket and body of the request, all quoted for a POSIX shell.
+    /// Multipart bodies and TLS settings are not included. See [`to_curl_command_redacted`](#method.to_curl_command_redacted)
+    /// to leave out secrets. Nothing is sent.
+    pub fn to_curl_command(&self) -> String {
+        self.curl_command(false)
+    }
+
+    /// Renders the request as an equivalent `curl` command line with all secrets
+    /// replaced by a placeholder.
+    ///
+    /// This hides passwords, cookie values and the values of the `Authorization`,
+    /// `Proxy-Authorization` and `Cookie` headers. See [`to_curl_command`](#method.to_curl_command)
+    /// for more information.
+    pub fn to_curl_command_redacted(&self) -> String {
+        self.curl_command(true)
+    }
+
+    /// Connects to the Unix domain socket at the given path instead of the
+    /// host and port of the URL, e.g. to talk to a local daemon.
+    ///
+    /// The host of the URL is then only used for the `Host` header, so a
+    /// placeholder like `http://localhost/` will do.
+    pub fn unix_socket(mut self, path: &Path) -> Self {
+        self.unix_socket = Some(path.to_owned());
+        self
+    }
+
+    /// Sets the `User-Agent` of the request.
+    ///
+    /// Defaults to [`USER_AGENT`](constant.USER_AGENT.html). A `User-Agent`
+    /// set through [`header`](#method.header) takes precedence.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_owned();
+        self
+    }
+
     /// Uses the given cURL handle in the request process reusing its resources
     /// and improving performance.
     ///
@@ -312,13 +1692,274 @@ impl Request {
         self
     }
 
+    /// Sets the option whether cURL prints verbose information about the transfer,
+    /// e.g. the TLS handshake and all headers, to stderr.
+    ///
+    /// Defaults to `false`. See [`Request::debug_callback`](#method.debug_callback)
+    /// to capture the output instead.
+    pub fn verbose(mut self, enabled: bool) -> Self {
+        self.verbose = enabled;
+        self
+    }
+
+    /// Checks whether the request can be sent again, which is not the case
+    /// if its body is streamed from a reader.
+    pub(crate) fn is_repeatable(&self) -> bool {
+        self.body_reader.is_none()
+    }
+
+    /// Sends the request through the given session, re-issuing it according
+    /// to the retry policy with the delays driven by the given event loop.
+    pub(crate) fn send_retrying(mut self, session: &Session, h: &Handle) -> ResponseFuture {
+        let policy = match self.retry.take() {
+            Some(policy) if self.is_repeatable() => policy,
+            _ => return self.send_with_session(session)
+        };
+        let session = session.clone();
+        let h = h.clone();
+        let start = Instant::now();
+
+        let attempts = future::loop_fn((self, 1), move |(request, attempt)| {
+            let next = request.clone();
+            let policy = policy.clone();
+            let h = h.clone();
+
+            request.send_with_session(&session).then(move |result| {
+                // Delays too long to be represented, e.g. from a bogus `Retry-After`, end the retries
+                let retry_at = policy.delay(attempt, &result)
+                    .filter(|&delay| policy.within_deadline(start.elapsed(), delay))
+                    .and_then(|delay| Instant::now().checked_add(delay));
+                let retry_at = match retry_at {
+                    Some(retry_at) if policy.should_retry(attempt, &result) => retry_at,
+                    _ => return Either::A(future::ok(Loop::Break(result)))
+                };
+                match Timeout::new_at(retry_at, &h) {
+                    Ok(timeout) => Either::B(timeout.then(move |_| Ok(Loop::Continue((next, attempt + 1))))),
+                    Err(err) => Either::A(future::ok(Loop::Break(Err(err.into()))))
+                }
+            })
+        });
+        Box::new(attempts.and_then(|result| result))
+    }
+
+    /// Derives the URL including the parameters, the headers, the cookies and
+    /// the possibly compressed given body the request is sent with.
+    fn assemble(&self, body: Option<Vec<u8>>) -> Assembled {
+        let mut url = self.url.clone();
+        append_params(&mut url, &self.params, self.encoding_set);
+
+        // A body from a reader without a size is framed by chunked transfer encoding instead
+        let chunked = self.body_reader.is_some() && self.content_length.is_none();
+        let mut headers: Vec<_> = self.headers.iter()
+            .filter(|(name, _)| !(chunked && name.trim().eq_ignore_ascii_case("Content-Length")))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .collect();
+        let body = match (body, self.body_encoding) {
+            (Some(body), Some(encoding)) => {
+                headers.push(("Content-Encoding".to_owned(), encoding.to_string()));
+                Some(encoding.encode(&body))
+            },
+            (body, _) => body
+        };
+
+        let mut cookies: Vec<_> = self.cookies.iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        if let Some(header) = self.cookie_jar.as_ref().and_then(|jar| jar.cookie_header(&url)) {
+            cookies.push(header);
+        }
+
+        Assembled {
+            body,
+            cookies: cookies.join("; "),
+            headers,
+            url
+        }
+    }
+
+    /// Renders the request as `curl` command line, optionally hiding secrets.
+    fn curl_command(&self, redact: bool) -> String {
+        let secret = |value: &str| if redact { REDACTED.to_owned() } else { value.to_owned() };
+        let mut args = vec!["curl".to_owned(), "-X".to_owned(), quote(self.method.as_ref())];
+
+        if self.follow_redirects {
+            args.push("-L".to_owned());
+            args.push("--max-redirs".to_owned());
+            args.push(self.max_redirects.to_string());
+        }
+        if self.accept_invalid_certs {
+            args.push("-k".to_owned());
+        }
+        if let Some(ref ciphers) = self.ciphers {
+            args.push("--ciphers".to_owned());
+            args.push(quote(ciphers));
+        }
+        if let Some(ref ciphers) = self.tls13_ciphers {
+            args.push("--tls13-ciphers".to_owned());
+            args.push(quote(ciphers));
+        }
+        let tls_version = |version| match version {
+            TlsVersion::Tls10 => "1.0",
+            TlsVersion::Tls11 => "1.1",
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3"
+        };
+        if let Some(version) = self.min_tls_version {
+            args.push(format!("--tlsv{}", tls_version(version)));
+        }
+        if let Some(version) = self.max_tls_version {
+            args.push("--tls-max".to_owned());
+            args.push(tls_version(version).to_owned());
+        }
+        if let Some((scheme, ref username, ref password)) = self.auth {
+            if scheme == AuthScheme::Digest {
+                args.push("--digest".to_owned());
+            }
+            args.push("-u".to_owned());
+            args.push(quote(&format!("{}:{}", username, secret(password))));
+        }
+        if let Some(ref proxy) = self.proxy {
+            let mut proxy = proxy.clone();
+            if redact && proxy.password().is_some() {
+                let _ = proxy.set_password(Some(REDACTED));
+            }
+            args.push("-x".to_owned());
+            args.push(quote(proxy.as_str()));
+            if let Some((ref username, ref password)) = self.proxy_credentials {
+                args.push("-U".to_owned());
+                args.push(quote(&format!("{}:{}", username, secret(password))));
+            }
+        }
+        if !self.pinned_public_keys.is_empty() {
+            args.push("--pinnedpubkey".to_owned());
+            args.push(quote(&self.pinned_public_keys.join(";")));
+        }
+        if let Some(ref path) = self.unix_socket {
+            args.push("--unix-socket".to_owned());
+            args.push(quote(&path.to_string_lossy()));
+        }
+        args.push("-A".to_owned());
+        args.push(quote(&self.user_agent));
+        if let Some(ref referer) = self.referer {
+            args.push("-e".to_owned());
+            args.push(quote(referer));
+        }
+        let Assembled { body, cookies, headers, url } = self.assemble(self.body.clone());
+        for (name, value) in &headers {
+            let value = if ["Authorization", "Proxy-Authorization", "Cookie"].iter().any(|h| name.eq_ignore_ascii_case(h)) {
+                secret(value)
+            } else {
+                value.clone()
+            };
+            args.push("-H".to_owned());
+            args.push(quote(&format!("{}: {}", name, value)));
+        }
+        if !cookies.is_empty() {
+            let cookies = cookies.split("; ")
+                .map(|pair| match pair.find('=') {
+                    Some(pos) => format!("{}={}", &pair[..pos], secret(&pair[pos + 1..])),
+                    None => pair.to_owned()
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            args.push("-b".to_owned());
+            args.push(quote(&cookies));
+        }
+        if let Some(ref body) = body {
+            let body = String::from_utf8_lossy(body);
+            // cURL reads the body from a file if it starts with an @
+            args.push(if body.starts_with('@') { "--data-raw" } else { "--data" }.to_owned());
+            args.push(quote(&body));
+        }
+
+        args.push(quote(url.as_str()));
+        args.join(" ")
+    }
+
     #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
-    fn set_json(mut self, body: Vec<u8>) -> Self {
-        self.body = Some(body);
+    fn set_json(mut self, body: Result<Vec<u8>, Box<dyn Error + Send + Sync>>) -> Self {
+        match body {
+            Ok(body) => {
+                self.body = Some(body);
+                self.body_error = None;
+                self.body_reader = None;
+            },
+            Err(err) => self.body_error = Some(err)
+        }
         self.header("Content-Type", "application/json")
     }
 }
 
+impl Clone for Request {
+    /// Copies the request configuration.
+    ///
+    /// The body and all headers are copied, so cloning a request with a large body
+    /// is as expensive as copying the body. A body reader is shared with the clone
+    /// instead. A cURL handle passed to [`use_handle`](#method.use_handle) is not
+    /// copied, since it cannot be shared, the clone creates a fresh handle when it is sent.
+    fn clone(&self) -> Request {
+        Request {
+            accept_encoding: self.accept_encoding.clone(),
+            accept_invalid_certs: self.accept_invalid_certs,
+            auth: self.auth.clone(),
+            body: self.body.clone(),
+            body_encoding: self.body_encoding,
+            // The original error might not be cloneable, so only its message is kept
+            body_error: self.body_error.as_ref().map(|err| err.to_string().into()),
+            body_reader: self.body_reader.clone(),
+            ca_cert: self.ca_cert.clone(),
+            ca_cert_bytes: self.ca_cert_bytes.clone(),
+            ciphers: self.ciphers.clone(),
+            client_cert: self.client_cert.clone(),
+            client_cert_type: self.client_cert_type,
+            configure: self.configure.clone(),
+            connect_timeout: self.connect_timeout,
+            content_length: self.content_length,
+            connect_to: self.connect_to.clone(),
+            cookie_jar: self.cookie_jar.clone(),
+            cookies: self.cookies.clone(),
+            debug_callback: self.debug_callback.clone(),
+            decompress: self.decompress,
+            dns_cache_timeout: self.dns_cache_timeout,
+            dns_servers: self.dns_servers.clone(),
+            encoding_set: self.encoding_set,
+            follow_redirects: self.follow_redirects,
+            handle: None,
+            headers: self.headers.clone(),
+            http_version: self.http_version,
+            interface: self.interface.clone(),
+            invalid_host: self.invalid_host.clone(),
+            ip_resolve: self.ip_resolve,
+            lowspeed_limits: self.lowspeed_limits,
+            max_body_size: self.max_body_size,
+            max_recv_speed: self.max_recv_speed,
+            max_redirects: self.max_redirects,
+            max_send_speed: self.max_send_speed,
+            max_tls_version: self.max_tls_version,
+            method: self.method.clone(),
+            min_tls_version: self.min_tls_version,
+            multipart: self.multipart.clone(),
+            no_proxy: self.no_proxy.clone(),
+            on_progress: self.on_progress.clone(),
+            params: self.params.clone(),
+            pinned_public_keys: self.pinned_public_keys.clone(),
+            proxy: self.proxy.clone(),
+            proxy_credentials: self.proxy_credentials.clone(),
+            query_error: self.query_error.as_ref().map(|err| err.to_string().into()),
+            range: self.range.clone(),
+            referer: self.referer.clone(),
+            resolve: self.resolve.clone(),
+            retry: self.retry.clone(),
+            timeout: self.timeout,
+            tls13_ciphers: self.tls13_ciphers.clone(),
+            unix_socket: self.unix_socket.clone(),
+            url: self.url.clone(),
+            user_agent: self.user_agent.clone(),
+            verbose: self.verbose
+        }
+    }
+}
+
 impl Debug for Request {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
         let len = if let Some(ref body) = self.body {
@@ -328,12 +1969,16 @@ impl Debug for Request {
         };
         fmt.debug_struct(stringify!(Request))
             .field("body_len", &len)
+            .field("connect_timeout", &self.connect_timeout)
             .field("follow_redirects", &self.follow_redirects)
             .field("headers", &self.headers)
             .field("method", &self.method)
             .field("params", &self.params)
+            .field("proxy", &self.proxy.as_ref().map(|proxy| proxy.host_str()))
             .field("reuses_handle", &self.handle.is_some())
             .field("url", &self.url)
+            .field("user_agent", &self.user_agent)
+            .field("verbose", &self.verbose)
             .finish()
     }
 }
@@ -342,4 +1987,507 @@ impl Display for Request {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
         write!(fmt, "{} {}", self.method, self.url)
     }
-}
\ No newline at end of file
+}
+
+/// Aborts a request sent through [`Request::send_cancelable`](struct.Request.html#method.send_cancelable).
+///
+/// Dropping the handle without calling [`cancel`](#method.cancel) lets the
+/// request run to completion.
+#[derive(Debug)]
+pub struct CancelHandle {
+    tx: oneshot::Sender<()>
+}
+
+impl CancelHandle {
+    /// Aborts the transfer and resolves the future of the request to
+    /// [`RequestError::Cancelled`](enum.RequestError.html#variant.Cancelled).
+    ///
+    /// This has no effect if the request has already completed.
+    pub fn cancel(self) {
+        let _ = self.tx.send(());
+    }
+}
+
+/// The URL, headers, cookies and body a request is sent with.
+///
+/// Sending, [`Request::build`](struct.Request.html#method.build) and the `curl`
+/// command line all derive these through the same function so they match.
+struct Assembled {
+    body: Option<Vec<u8>>,
+    cookies: String,
+    headers: Vec<(String, String)>,
+    url: Url
+}
+
+/// The method, URL, headers and body of a [`Request`](struct.Request.html),
+/// as it would be sent.
+///
+/// The URL includes the query parameters and the body is compressed if
+/// [`Request::compress_body`](struct.Request.html#method.compress_body) is set.
+/// Headers cURL derives from the transfer itself, like `Host`, `Content-Length`
+/// or `Accept-Encoding`, are not included. See [`Request::build`](struct.Request.html#method.build)
+/// on how to get one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreparedRequest {
+    body: Option<Vec<u8>>,
+    headers: Vec<(String, String)>,
+    method: Method,
+    url: Url
+}
+
+impl PreparedRequest {
+    /// Gets the body of the request.
+    ///
+    /// This is `None` if the request has no body, or if it is a multipart
+    /// form, streamed from a reader or failed to encode.
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+
+    /// Attempts to get a single header value.
+    ///
+    /// Header names are matched case-insensitively. If there are multiple
+    /// headers with the same name, this method returns the first one.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers().find(|&(key, _)| key.eq_ignore_ascii_case(name))
+                      .map(|(_, value)| value)
+    }
+
+    /// Gets all request headers as name-value-pairs in the order they are sent in.
+    pub fn headers<'a>(&'a self) -> impl ExactSizeIterator<Item = (&'a str, &'a str)> + 'a {
+        self.headers.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Gets the method of the request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Gets the URL of the request including the query parameters.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+/// Sends all given requests concurrently through a single new `Session` on the specified
+/// event loop and returns a future that resolves to their results.
+///
+/// The results are in the same order as the requests. A failing request does not fail the
+/// whole batch, its error is reported in its result instead, so the returned future itself
+/// never fails.
+///
+/// ## Panics
+/// Panics in case of native exceptions in cURL.
+pub fn send_all<I: IntoIterator<Item = Request>>(requests: I, h: Handle) -> BatchFuture {
+    let session = Session::new(h.clone());
+    let futures = requests.into_iter()
+        .map(|request| request.send_retrying(&session, &h).then(Ok))
+        .collect::<Vec<_>>();
+    Box::new(future::join_all(futures))
+}
+
+/// Wraps IPv6 addresses in brackets, as cURL expects in host lists.
+fn bracket_ipv6(host: &str) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_owned()
+    }
+}
+
+/// Checks whether the given string is a host name or IP address, optionally
+/// followed by a port.
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty() &&
+        !host.contains(|c: char| c.is_whitespace() || c.is_control() || "/\\?#@".contains(c)) &&
+        Url::parse(&format!("http://{}/", host)).is_ok()
+}
+
+/// Quotes the given string for a POSIX shell.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Checks whether a timed out transfer has been aborted by the lowspeed limit
+/// rather than the connect timeout or the overall timeout.
+fn is_stalled(easy: &Easy, timeout: Option<Duration>) -> bool {
+    let connected = easy.connect_time().is_ok_and(|time| time > Duration::from_secs(0));
+    let total = easy.total_time().unwrap_or_default();
+    connected && timeout.is_none_or(|timeout| total < timeout)
+}
+
+/// Sets `CURLOPT_XFERINFOFUNCTION`, which the `curl` crate doesn't expose, to
+/// report the progress of the transfer to the given function.
+fn set_xferinfo(easy: &mut Easy, f: Arc<ProgressFn>) -> Result<(), CurlError> {
+    const CURLOPT_XFERINFODATA: curl_sys::CURLoption = curl_sys::CURLOPTTYPE_OBJECTPOINT + 57;
+    const CURLOPT_XFERINFOFUNCTION: curl_sys::CURLoption = curl_sys::CURLOPTTYPE_FUNCTIONPOINT + 219;
+
+    extern "C" fn xferinfo(data: *mut c_void,
+                           dltotal: curl_sys::curl_off_t,
+                           dlnow: curl_sys::curl_off_t,
+                           ultotal: curl_sys::curl_off_t,
+                           ulnow: curl_sys::curl_off_t) -> c_int {
+        let f = unsafe { &*(data as *const Arc<ProgressFn>) };
+        let count = |bytes: curl_sys::curl_off_t| bytes.max(0) as u64;
+        // Unwinding into cURL is undefined behavior, so a panic aborts the transfer instead
+        match panic::catch_unwind(AssertUnwindSafe(|| f(count(dltotal), count(dlnow), count(ultotal), count(ulnow)))) {
+            Ok(_) => 0,
+            Err(_) => 1
+        }
+    }
+
+    // The boxed function is kept alive by the progress function of the handle,
+    // which cURL doesn't call while the transfer info function is set.
+    let data = Box::new(f);
+    let ptr = &*data as *const Arc<ProgressFn> as *mut c_void;
+    let cb = xferinfo as extern "C" fn(_, _, _, _, _) -> _;
+    match unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_XFERINFOFUNCTION, cb) } {
+        curl_sys::CURLE_OK => {},
+        code => return Err(CurlError::new(code))
+    }
+    easy.progress_function(move |_, _, _, _| {
+        let _ = &data;
+        true
+    })?;
+    match unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_XFERINFODATA, ptr) } {
+        curl_sys::CURLE_OK => Ok(()),
+        code => Err(CurlError::new(code))
+    }
+}
+
+/// Sets `CURLOPT_DNS_CACHE_TIMEOUT`, since the `curl` crate only accepts a
+/// `Duration`, which cannot express caching forever.
+fn set_dns_cache_timeout(easy: &mut Easy, secs: i64) -> Result<(), CurlError> {
+    // Values beyond a 32-bit C long are close enough to forever
+    let secs = if secs < 0 {
+        -1
+    } else {
+        c_long::try_from(secs).unwrap_or(c_long::MAX)
+    };
+    match unsafe { curl_sys::curl_easy_setopt(easy.raw(), curl_sys::CURLOPT_DNS_CACHE_TIMEOUT, secs) } {
+        curl_sys::CURLE_OK => Ok(()),
+        code => Err(CurlError::new(code))
+    }
+}
+
+/// Sets `CURLOPT_TLS13_CIPHERS`, which the `curl` crate doesn't expose.
+fn set_tls13_ciphers(easy: &mut Easy, ciphers: &str) -> Result<(), CurlError> {
+    const CURLOPT_TLS13_CIPHERS: curl_sys::CURLoption = curl_sys::CURLOPTTYPE_OBJECTPOINT + 276;
+
+    let ciphers = CString::new(ciphers)?;
+    // cURL copies the string, so it only has to outlive the call
+    match unsafe { curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_TLS13_CIPHERS, ciphers.as_ptr()) } {
+        curl_sys::CURLE_OK => Ok(()),
+        code => Err(CurlError::new(code))
+    }
+}
+
+/// Builds a cURL list from the given entries.
+fn to_list(entries: &[String]) -> Result<List, CurlError> {
+    let mut list = List::new();
+    for entry in entries {
+        list.append(entry)?;
+    }
+    Ok(list)
+}
+
+/// Checks whether the given host matches the given `NO_PROXY` list.
+fn bypasses_proxy(no_proxy: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.').to_ascii_lowercase();
+    no_proxy.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|entry| entry.trim_start_matches('.').trim_end_matches('.').to_ascii_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*" || host == entry ||
+                (host.ends_with(&entry) && host[..host.len() - entry.len()].ends_with('.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bypasses_proxy;
+    use ::str::get;
+
+    #[test]
+    fn accept() {
+        let request = get("https://httpbin.org/get")
+            .accept("text/html".parse().unwrap())
+            .header("X-Custom", "value")
+            .accept_json();
+
+        assert_eq!(request.headers, vec![
+            ("X-Custom".to_owned(), "value".to_owned()),
+            ("Accept".to_owned(), "application/json".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn bearer_auth() {
+        let request = get("https://httpbin.org/bearer")
+            .header("X-Custom", "value")
+            .bearer_auth("first")
+            .bearer_auth("second");
+
+        assert_eq!(request.headers, vec![
+            ("X-Custom".to_owned(), "value".to_owned()),
+            ("Authorization".to_owned(), "Bearer second".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn build() {
+        use ::Encoding;
+        use ::Method;
+        use ::str::post;
+        use USER_AGENT;
+
+        let prepared = post("https://httpbin.org/post?a=1")
+            .param("b", "2 3")
+            .header("X-Custom", " value ")
+            .cookie("session", "abc")
+            .body("data")
+            .build();
+
+        assert_eq!(prepared.method(), &Method::Post);
+        assert_eq!(prepared.url().as_str(), "https://httpbin.org/post?a=1&b=2+3");
+        assert_eq!(prepared.headers().collect::<Vec<_>>(), vec![
+            ("User-Agent", USER_AGENT),
+            ("X-Custom", "value"),
+            ("Cookie", "session=abc")
+        ]);
+        assert_eq!(prepared.body(), Some(&b"data"[..]));
+
+        let prepared = post("https://httpbin.org/post")
+            .body("data")
+            .compress_body(Encoding::Gzip)
+            .build();
+        assert_eq!(prepared.header("content-encoding"), Some("gzip"));
+        assert_eq!(prepared.body(), Some(&Encoding::Gzip.encode(b"data")[..]));
+
+        assert_eq!(get("https://httpbin.org/get").build().body(), None);
+
+        // Explicit headers override the defaults, like they do when sending
+        let prepared = get("https://httpbin.org/get")
+            .referer("https://example.com/")
+            .header("user-agent", "my-app/1.0")
+            .header("Referer", "https://example.org/")
+            .build();
+        assert_eq!(prepared.header("User-Agent"), Some("my-app/1.0"));
+        assert_eq!(prepared.headers().collect::<Vec<_>>(), vec![
+            ("user-agent", "my-app/1.0"),
+            ("Referer", "https://example.org/")
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde-serialization")]
+    fn build_json() {
+        use ::str::post;
+        use serde_json;
+        use std::collections::BTreeMap;
+
+        let mut data = BTreeMap::new();
+        data.insert("name", "tokio-request");
+
+        let prepared = post("https://httpbin.org/post").json(&data).build();
+        assert_eq!(prepared.header("Content-Type"), Some("application/json"));
+        assert_eq!(prepared.body(), Some(&serde_json::to_vec(&data).unwrap()[..]));
+    }
+
+    #[test]
+    fn clone() {
+        let request = get("https://httpbin.org/get")
+            .header("X-Custom", "value")
+            .param("page", "1")
+            .body(b"body");
+        let clone = request.clone().param("page", "2");
+
+        assert_eq!(request.params, vec![("page".to_owned(), "1".to_owned())]);
+        assert_eq!(clone.params, vec![
+            ("page".to_owned(), "1".to_owned()),
+            ("page".to_owned(), "2".to_owned())
+        ]);
+        assert_eq!(clone.headers, request.headers);
+        assert_eq!(clone.body, request.body);
+        assert_eq!(clone.method, request.method);
+    }
+
+    #[test]
+    fn connect_to_entries() {
+        let request = get("https://example.com/")
+            .connect_to("example.com", 443, "backend-1.example.com", 8443)
+            .connect_to("example.com", 80, "::1", 8080)
+            .connect_to("example.com", 443, "backend-2.example.com", 8443);
+        assert_eq!(request.connect_to, vec![
+            "example.com:443:backend-2.example.com:8443".to_owned(),
+            "example.com:80:[::1]:8080".to_owned()
+        ]);
+    }
+
+    #[test]
+    fn ciphers_empty() {
+        use tokio_core::reactor::Core;
+        use RequestError;
+
+        let mut evloop = Core::new().unwrap();
+        let request = get("https://httpbin.org/get").ciphers(" ").send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::EmptyCipherList) => {},
+            other => panic!("Expected an empty cipher list error, got {:?}", other)
+        }
+
+        let request = get("https://httpbin.org/get").tls13_ciphers("").send(evloop.handle());
+        match evloop.run(request) {
+            Err(RequestError::EmptyCipherList) => {},
+            other => panic!("Expected an empty cipher list error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn curl_command() {
+        use ::str::post;
+        use USER_AGENT;
+
+        let request = post("https://httpbin.org/post")
+            .header("Content-Type", "application/json")
+            .bearer_auth("secret")
+            .param("page", "1")
+            .body(r#"{"name":"it's"}"#);
+
+        assert_eq!(request.to_curl_command(), format!(
+            "curl -X 'POST' -L --max-redirs 10 -A '{}' -H 'Content-Type: application/json' \
+             -H 'Authorization: Bearer secret' --data '{{\"name\":\"it'\\''s\"}}' \
+             'https://httpbin.org/post?page=1'",
+            USER_AGENT
+        ));
+        assert_eq!(request.to_curl_command_redacted(), format!(
+            "curl -X 'POST' -L --max-redirs 10 -A '{}' -H 'Content-Type: application/json' \
+             -H 'Authorization: <redacted>' --data '{{\"name\":\"it'\\''s\"}}' \
+             'https://httpbin.org/post?page=1'",
+            USER_AGENT
+        ));
+
+        let request = post("https://httpbin.org/post")
+            .digest_auth("user", "pass")
+            .cookie("session", "abc")
+            .follow_redirects(false);
+        assert_eq!(request.to_curl_command_redacted(), format!(
+            "curl -X 'POST' --digest -u 'user:<redacted>' -A '{}' -b 'session=<redacted>' 'https://httpbin.org/post'",
+            USER_AGENT
+        ));
+    }
+
+    #[test]
+    fn expect_continue() {
+        let request = get("https://httpbin.org/post").expect_continue(false);
+        assert_eq!(request.headers, vec![("Expect".to_owned(), "".to_owned())]);
+
+        let request = request.expect_continue(false).expect_continue(true);
+        assert!(request.headers.is_empty());
+    }
+
+    #[test]
+    fn headers() {
+        let request = get("https://httpbin.org/headers")
+            .header("X-First", "1")
+            .headers(vec![("Accept", "text/html"), ("X-Second", "2"), ("Accept", "application/json")]);
+
+        assert_eq!(request.headers, vec![
+            ("X-First".to_owned(), "1".to_owned()),
+            ("Accept".to_owned(), "text/html".to_owned()),
+            ("X-Second".to_owned(), "2".to_owned()),
+            ("Accept".to_owned(), "application/json".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn header_semantics() {
+        let request = get("https://httpbin.org/headers")
+            .header("Content-Type", "text/plain")
+            .header("X-Custom", "value")
+            .header("content-type", "application/json");
+        assert_eq!(request.headers, vec![
+            ("X-Custom".to_owned(), "value".to_owned()),
+            ("content-type".to_owned(), "application/json".to_owned())
+        ]);
+
+        let request = request
+            .append_header("Accept", "text/html")
+            .append_header("accept", "application/json");
+        assert_eq!(request.headers.len(), 4);
+        assert_eq!(request.headers[3], ("accept".to_owned(), "application/json".to_owned()));
+
+        let request = request.remove_header("ACCEPT").remove_header("X-Missing");
+        assert_eq!(request.headers, vec![
+            ("X-Custom".to_owned(), "value".to_owned()),
+            ("content-type".to_owned(), "application/json".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn host_validation() {
+        use super::is_valid_host;
+
+        assert!(is_valid_host("example.com"));
+        assert!(is_valid_host("example.com:8080"));
+        assert!(is_valid_host("127.0.0.1"));
+        assert!(is_valid_host("[::1]:443"));
+
+        assert!(!is_valid_host(""));
+        assert!(!is_valid_host("exa mple.com"));
+        assert!(!is_valid_host("example.com/path"));
+        assert!(!is_valid_host("user@example.com"));
+        assert!(!is_valid_host("example.com:99999"));
+        assert!(!is_valid_host("::1"));
+        assert!(!is_valid_host("example.com\r\nX-Injected: 1"));
+    }
+
+    #[test]
+    fn no_proxy_matching() {
+        assert!(bypasses_proxy("*", "example.com"));
+        assert!(bypasses_proxy("localhost, example.com", "example.com"));
+        assert!(bypasses_proxy("example.com", "api.example.com"));
+        assert!(bypasses_proxy(".example.com", "example.com"));
+        assert!(bypasses_proxy(".example.com", "www.api.example.com"));
+        assert!(bypasses_proxy("EXAMPLE.com", "Example.COM."));
+        assert!(bypasses_proxy("127.0.0.1,::1", "[::1]"));
+
+        assert!(!bypasses_proxy("", "example.com"));
+        assert!(!bypasses_proxy("example.com", "badexample.com"));
+        assert!(!bypasses_proxy(".example.com", "example.org"));
+        assert!(!bypasses_proxy("api.example.com", "example.com"));
+        assert!(!bypasses_proxy("localhost,*.example.com", "example.org"));
+    }
+
+    #[test]
+    fn conditional_headers() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let request = get("https://httpbin.org/cache")
+            .if_none_match("first")
+            .if_none_match("abc")
+            .if_modified_since(UNIX_EPOCH + Duration::from_secs(1445412480));
+        assert_eq!(request.headers, vec![
+            ("If-None-Match".to_owned(), "\"abc\"".to_owned()),
+            ("If-Modified-Since".to_owned(), "Wed, 21 Oct 2015 07:28:00 GMT".to_owned())
+        ]);
+
+        assert_eq!(get("https://httpbin.org/cache").if_none_match("\"abc\"").headers[0].1, "\"abc\"");
+        assert_eq!(get("https://httpbin.org/cache").if_none_match("W/\"abc\"").headers[0].1, "W/\"abc\"");
+        assert_eq!(get("https://httpbin.org/cache").if_none_match("*").headers[0].1, "*");
+    }
+
+    #[test]
+    fn resolve_entries() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        let request = get("https://example.com/")
+            .resolve("example.com", 443, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+            .resolve("example.com", 80, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)))
+            .resolve("example.com", 443, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(request.resolve, vec![
+            "example.com:443:127.0.0.1,[::1]".to_owned(),
+            "example.com:80:127.0.0.2".to_owned()
+        ]);
+    }
+}
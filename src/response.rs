@@ -1,13 +1,30 @@
 //! The module that contains the code handling the HTTP response.
 
+use std::borrow::Cow;
 use std::convert::From;
 use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::net::IpAddr;
 use std::str;
+use std::time::{Duration, SystemTime};
 
+use {HttpVersion, Method};
+
+use cookie::Cookie;
+use curl::Error as CurlError;
 use curl::easy::Easy;
-use mime::Mime;
+use curl_sys;
+use encoding_rs::Encoding as Charset;
+use httpdate::parse_http_date;
+use error::RequestError;
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+use error::ApiError;
+use mime::{Attr, Mime};
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+use mime::{SubLevel, TopLevel};
+use status::StatusCode;
+use url::Url;
 
-#[cfg(feature = "rustc-serialization")]
+#[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
 use rustc_serialize;
 
 #[cfg(feature = "serde-serialization")]
@@ -15,48 +32,140 @@ use serde;
 #[cfg(feature = "serde-serialization")]
 use serde_json;
 
-#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
-use std::io::{Error, ErrorKind};
 
 /// Represents an HTTP response.
 pub struct Response {
     body: Vec<u8>,
+    bytes_downloaded: u64,
+    bytes_uploaded: u64,
+    effective_url: Option<Url>,
     handle: Easy,
     headers: Vec<(String, String)>,
-    status_code: u16
+    http_version: Option<HttpVersion>,
+    local_ip: Option<IpAddr>,
+    local_port: Option<u16>,
+    primary_ip: Option<IpAddr>,
+    primary_port: Option<u16>,
+    reason: Option<String>,
+    request_method: Method,
+    request_url: Url,
+    reused_connection: bool,
+    speed_download: f64,
+    speed_upload: f64,
+    status_code: u16,
+    timing: Timing,
+    trailers: Vec<(String, String)>
+}
+
+/// The time spent in the individual phases of a transfer.
+///
+/// All durations are measured from the start of the transfer, so every phase
+/// includes the time of the phases before it. Phases that did not happen,
+/// e.g. the TLS handshake of a plain HTTP request, are zero.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Timing {
+    /// The time until name resolution completed.
+    pub namelookup: Duration,
+    /// The time until the connection to the server was established.
+    pub connect: Duration,
+    /// The time until the TLS handshake completed.
+    pub appconnect: Duration,
+    /// The time until the request was about to be sent.
+    pub pretransfer: Duration,
+    /// The time until the first byte of the response was received.
+    pub starttransfer: Duration,
+    /// The total time of the transfer.
+    pub total: Duration
 }
 
 impl Response {
     /// Creates a `Response` from the results of a successful request.
     ///
     /// You usually don't create a response this way, but get one as result
-    /// from `Request.send(...)`.
-    pub fn new(mut easy: Easy, headers: Vec<String>, body: Vec<u8>) -> Response {
-        let headers =  {
-            let mut vec = Vec::new();
-            for header in headers {
-                let splitted: Vec<_> = header.splitn(2, ": ")
-                                             .map(|part| part.trim())
-                                             .filter(|part| part.len() > 0)
-                                             .collect();
-                if splitted.len() != 2 {
-                    continue;
-                }
-
-                vec.push((splitted[0].to_owned(), splitted[1].to_owned()));
-            }
-            vec
-        };
+    /// from `Request.send(...)`. The request is assumed to be a GET-Request
+    /// to the effective URL of the handle, or to `about:blank` if cURL does not
+    /// report an effective URL that can be parsed.
+    pub fn new(easy: Easy, headers: Vec<String>, body: Vec<u8>) -> Response {
+        let (ResponseHead { headers, http_version, reason, .. }, trailers) = ResponseHead::with_trailers(&headers);
+        let bytes_downloaded = get_off_t(&easy, CURLINFO_SIZE_DOWNLOAD_T).unwrap_or(0);
+        let bytes_uploaded = get_off_t(&easy, CURLINFO_SIZE_UPLOAD_T).unwrap_or(0);
+        let effective_url = easy.effective_url()
+                                .ok()
+                                .and_then(|url| url)
+                                .and_then(|url| Url::parse(url).ok());
+        let local_ip = easy.local_ip()
+                           .ok()
+                           .and_then(|ip| ip)
+                           .and_then(|ip| ip.parse().ok());
+        let local_port = easy.local_port().ok().filter(|&port| port != 0);
+        let primary_ip = easy.primary_ip()
+                             .ok()
+                             .and_then(|ip| ip)
+                             .and_then(|ip| ip.parse().ok());
+        let primary_port = easy.primary_port().ok().filter(|&port| port != 0);
+        let request_url = effective_url.clone().unwrap_or_else(|| "about:blank".parse().unwrap());
+        let reused_connection = easy.num_connects().map(|connects| connects == 0).unwrap_or(false);
+        let speed_download = get_off_t(&easy, CURLINFO_SPEED_DOWNLOAD_T).unwrap_or(0) as f64;
+        let speed_upload = get_off_t(&easy, CURLINFO_SPEED_UPLOAD_T).unwrap_or(0) as f64;
         let status_code = easy.response_code().expect("Failed to get the response status code from cURL.") as u16;
+        let timing = Timing {
+            namelookup: easy.namelookup_time().unwrap_or_default(),
+            connect: easy.connect_time().unwrap_or_default(),
+            appconnect: easy.appconnect_time().unwrap_or_default(),
+            pretransfer: easy.pretransfer_time().unwrap_or_default(),
+            starttransfer: easy.starttransfer_time().unwrap_or_default(),
+            total: easy.total_time().unwrap_or_default()
+        };
         Response {
-            body: body,
+            body,
+            bytes_downloaded,
+            bytes_uploaded,
+            effective_url,
             handle: easy,
-            headers: headers,
-            status_code: status_code
+            headers,
+            http_version,
+            local_ip,
+            local_port,
+            primary_ip,
+            primary_port,
+            reason,
+            request_method: Method::default(),
+            request_url,
+            reused_connection,
+            speed_download,
+            speed_upload,
+            status_code,
+            timing,
+            trailers
         }
     }
 
+    /// Records the method and URL of the request that produced the response.
+    pub(crate) fn with_request(mut self, method: Method, url: Url) -> Response {
+        self.request_method = method;
+        self.request_url = url;
+        self
+    }
+
+    /// Parses the methods the server supports for the resource from the `Allow`
+    /// header, e.g. in response to an OPTIONS request.
+    ///
+    /// Unknown methods are returned as [`Method::Custom`](enum.Method.html#variant.Custom).
+    /// Returns an empty list if the header is missing.
+    pub fn allowed_methods(&self) -> Vec<Method> {
+        self.header_all("Allow")
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(|method| method.trim())
+            .filter(|method| !method.is_empty())
+            .filter_map(|method| method.parse().ok())
+            .collect()
+    }
+
     /// Gets the response body's bytes.
+    ///
+    /// cURL removes the chunked transfer encoding, so this is the decoded body
+    /// without chunk sizes, extensions or trailers.
     pub fn body(&self) -> &[u8] {
         &self.body
     }
@@ -71,21 +180,71 @@ impl Response {
         str::from_utf8(self.body()).ok()
     }
 
+    /// Gets the amount of body bytes received from the server.
+    ///
+    /// This counts the bytes as they were transferred, i.e. before decompressing
+    /// a body sent with a `Content-Encoding`, and excludes the headers.
+    pub fn bytes_downloaded(&self) -> u64 {
+        self.bytes_downloaded
+    }
+
+    /// Gets the amount of body bytes sent to the server.
+    pub fn bytes_uploaded(&self) -> u64 {
+        self.bytes_uploaded
+    }
+
+    /// Gets the charset declared in the `Content-Type` header, e.g. `"utf-8"`.
+    ///
+    /// Returns `None` if there is no content type or if it has no charset parameter.
+    pub fn charset(&self) -> Option<String> {
+        self.content_type()
+            .and_then(|mime| mime.get_param(Attr::Charset).map(|charset| charset.as_str().to_owned()))
+    }
+
+    /// Parses the cookies set by the server via `Set-Cookie` headers.
+    ///
+    /// Malformed cookies are skipped.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.header_all("Set-Cookie")
+            .into_iter()
+            .filter_map(Cookie::parse)
+            .collect()
+    }
+
+    /// Gets the size of the body in bytes as declared in the `Content-Length` header.
+    ///
+    /// This is also available for responses to HEAD requests, which have no body.
+    /// Returns `None` if the header is missing or invalid.
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("Content-Length").and_then(|len| len.trim().parse().ok())
+    }
+
     /// Retreives the content type, if there is one.
     ///
     /// This function also returns none if there has been an error parsing
-    /// the mime type.
+    /// the mime type. See [`Response::charset`](#method.charset) to get
+    /// the charset parameter.
     pub fn content_type(&self) -> Option<Mime> {
         self.header("Content-Type")
             .and_then(|h| h.parse::<Mime>().ok())
     }
 
+    /// Gets the URL the response was actually retreived from.
+    ///
+    /// This differs from the request URL if cURL followed any redirects. Falls
+    /// back to the request URL if cURL reported an effective URL that cannot be
+    /// parsed.
+    pub fn effective_url(&self) -> &Url {
+        self.effective_url.as_ref().unwrap_or(&self.request_url)
+    }
+
     /// Returns `Ok` in case of a successful status code and `Err` if not.
     ///
     /// This returns the `Response` in both cases and uses the `Ok`
     /// and `Err` variants to indicate the result. See
     /// [`Response::is_success`](struct.Response.html#method.is_success)
     /// for more information.
+    #[allow(clippy::result_large_err)]
     pub fn ensure_success(self) -> Result<Response, Response> {
         if self.is_success() {
             Ok(self)
@@ -94,77 +253,333 @@ impl Response {
         }
     }
 
+    /// Turns a response with an unsuccessful status code into an error.
+    ///
+    /// Returns the response itself if the status code represents a success
+    /// and [`RequestError::Status`](enum.RequestError.html#variant.Status)
+    /// otherwise. This makes it easy to treat non-2xx responses as failures
+    /// in a chain of futures:
+    ///
+    /// ```rust,ignore
+    /// let future = get("https://httpbin.org/get")
+    ///                 .send(evloop.handle())
+    ///                 .and_then(Response::error_for_status);
+    /// ```
+    pub fn error_for_status(self) -> Result<Response, RequestError> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(RequestError::Status(self.status_code, self.effective_url.unwrap_or(self.request_url)))
+        }
+    }
+
     /// Attempts to get a single header value.
     ///
-    /// If there are multiple headers with the same name, this method returns
-    /// the first one. If you need to get access to the other values, use
-    /// [`Response::headers()`](struct.Response.html#method.headers).
-    pub fn header(&self, name: &str) -> Option<&String> {
-        self.headers.iter().filter(|kvp| kvp.0 == name)
-                           .nth(0)
-                           .map(|kvp| &kvp.1)
+    /// Header names are matched case-insensitively. If there are multiple
+    /// headers with the same name, this method returns the first one. If you
+    /// need to get access to the other values, use
+    /// [`Response::header_all()`](struct.Response.html#method.header_all).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_all(name).into_iter().next()
+    }
+
+    /// Gets the values of all headers with the given name.
+    ///
+    /// Header names are matched case-insensitively. The values are
+    /// returned in the order they were received in.
+    pub fn header_all(&self, name: &str) -> Vec<&str> {
+        self.headers().filter(|&(key, _)| key.eq_ignore_ascii_case(name))
+                      .map(|(_, value)| value)
+                      .collect()
+    }
+
+    /// Gets all response headers as name-value-pairs.
+    pub fn headers<'a>(&'a self) -> impl ExactSizeIterator<Item = (&'a str, &'a str)> + 'a {
+        self.headers.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Gets the HTTP version of the response.
+    ///
+    /// This is `None` if the version is unknown to this library. Responses
+    /// received via HTTP/2 always report
+    /// [`HttpVersion::Http2`](enum.HttpVersion.html#variant.Http2).
+    pub fn http_version(&self) -> Option<HttpVersion> {
+        self.http_version
+    }
+
+    /// Consumes the response and returns the body's bytes without copying them.
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
     }
 
-    /// Gets all response headers.
-    pub fn headers(&self) -> &Vec<(String, String)> {
-        &self.headers
+    /// Checks whether the server answered a conditional request with
+    /// `304 Not Modified`.
+    pub fn is_not_modified(&self) -> bool {
+        self.status() == StatusCode::NOT_MODIFIED
     }
 
     /// Checks whether the returned status code represents a success
     /// (HTTP status code 2xx) or not.
     pub fn is_success(&self) -> bool {
-        match self.status_code {
-            200...299 => true,
-            _ => false
-        }
+        self.status().is_success()
     }
 
     /// Attempts to decode the response body from JSON to an
     /// object of the given type.
     ///
-    /// Returns `ErrorKind::InvalidData` when the server response could not
-    /// be read as UTF-8 string or if it could not be deserialized from JSON.
-    #[cfg(feature = "rustc-serialization")]
-    pub fn json<T: rustc_serialize::Decodable>(&self) -> Result<T, Error> {
-        let string = try!(str::from_utf8(&self.body).map_err(|err| Error::new(ErrorKind::InvalidData, err)));
-        rustc_serialize::json::decode(string).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    /// Returns [`RequestError::Decode`](enum.RequestError.html#variant.Decode) when
+    /// the server declared a `Content-Type` other than JSON, when the server response
+    /// could not be read as UTF-8 string or if it could not be deserialized from JSON.
+    #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+    pub fn json<T: rustc_serialize::Decodable>(&self) -> Result<T, RequestError> {
+        self.ensure_json()?;
+        let string = str::from_utf8(&self.body).map_err(|err| RequestError::Decode(Box::new(err)))?;
+        rustc_serialize::json::decode(string).map_err(|err| RequestError::Decode(Box::new(err)))
     }
 
     /// Attempts to decode the response body from JSON to an
     /// object of the given type.
     ///
-    /// Returns `ErrorKind::InvalidData` when the server response could not
-    /// be read as UTF-8 string or if it could not be deserialized from JSON.
+    /// Returns [`RequestError::Decode`](enum.RequestError.html#variant.Decode) when
+    /// the server declared a `Content-Type` other than JSON, when the server response
+    /// could not be read as UTF-8 string or if it could not be deserialized from JSON.
     #[cfg(feature = "serde-serialization")]
-    pub fn json<T: serde::Deserialize>(&self) -> Result<T, Error> {
-        serde_json::from_slice(self.body()).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    pub fn json<T: serde::Deserialize>(&self) -> Result<T, RequestError> {
+        self.ensure_json()?;
+        serde_json::from_slice(self.body()).map_err(|err| RequestError::Decode(Box::new(err)))
+    }
+
+    /// Decodes the response body from JSON to an object of type `T` if the status
+    /// code represents a success, and to an error object of type `E` otherwise.
+    ///
+    /// This suits APIs that describe failures in the response body. Unsuccessful
+    /// responses yield [`ApiError::Status`](enum.ApiError.html#variant.Status)
+    /// with the status code and the error object. If the error object cannot be
+    /// decoded, see [`Response::json`](#method.json), this returns
+    /// [`ApiError::StatusUndecodable`](enum.ApiError.html#variant.StatusUndecodable)
+    /// with the status code instead, and if the success object cannot be decoded
+    /// [`ApiError::Request`](enum.ApiError.html#variant.Request).
+    #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+    pub fn json_or_error<T, E>(&self) -> Result<T, ApiError<E>>
+            where T: rustc_serialize::Decodable,
+                  E: rustc_serialize::Decodable {
+        if self.is_success() {
+            Ok(self.json()?)
+        } else {
+            Err(match self.json() {
+                Ok(body) => ApiError::Status(self.status(), body),
+                Err(err) => ApiError::StatusUndecodable(self.status(), err)
+            })
+        }
+    }
+
+    /// Decodes the response body from JSON to an object of type `T` if the status
+    /// code represents a success, and to an error object of type `E` otherwise.
+    ///
+    /// This suits APIs that describe failures in the response body. Unsuccessful
+    /// responses yield [`ApiError::Status`](enum.ApiError.html#variant.Status)
+    /// with the status code and the error object. If the error object cannot be
+    /// decoded, see [`Response::json`](#method.json), this returns
+    /// [`ApiError::StatusUndecodable`](enum.ApiError.html#variant.StatusUndecodable)
+    /// with the status code instead, and if the success object cannot be decoded
+    /// [`ApiError::Request`](enum.ApiError.html#variant.Request).
+    #[cfg(feature = "serde-serialization")]
+    pub fn json_or_error<T, E>(&self) -> Result<T, ApiError<E>>
+            where T: serde::Deserialize,
+                  E: serde::Deserialize {
+        if self.is_success() {
+            Ok(self.json()?)
+        } else {
+            Err(match self.json() {
+                Ok(body) => ApiError::Status(self.status(), body),
+                Err(err) => ApiError::StatusUndecodable(self.status(), err)
+            })
+        }
     }
 
     /// Attempts to decode the response body from JSON into an abstract
     /// JSON representation.
     ///
-    /// Returns `ErrorKind::InvalidData` when the server response could not
-    /// be read as UTF-8 string or if it could not be deserialized from JSON.
-    #[cfg(feature = "rustc-serialization")]
-    pub fn json_value(&self) -> Result<rustc_serialize::json::Json, Error> {
-        let string = try!(str::from_utf8(&self.body).map_err(|err| Error::new(ErrorKind::InvalidData, err)));
-        rustc_serialize::json::Json::from_str(string).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    /// Returns [`RequestError::Decode`](enum.RequestError.html#variant.Decode) when
+    /// the server declared a `Content-Type` other than JSON, when the server response
+    /// could not be read as UTF-8 string or if it could not be deserialized from JSON.
+    #[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+    pub fn json_value(&self) -> Result<rustc_serialize::json::Json, RequestError> {
+        self.ensure_json()?;
+        let string = str::from_utf8(&self.body).map_err(|err| RequestError::Decode(Box::new(err)))?;
+        rustc_serialize::json::Json::from_str(string).map_err(|err| RequestError::Decode(Box::new(err)))
     }
 
     /// Attempts to decode the response body from JSON into an abstract
     /// JSON representation.
     ///
-    /// Returns `ErrorKind::InvalidData` when the server response could not
-    /// be read as UTF-8 string or if it could not be deserialized from JSON.
+    /// Returns [`RequestError::Decode`](enum.RequestError.html#variant.Decode) when
+    /// the server declared a `Content-Type` other than JSON, when the server response
+    /// could not be read as UTF-8 string or if it could not be deserialized from JSON.
     #[cfg(feature = "serde-serialization")]
-    pub fn json_value(&self) -> Result<serde_json::Value, Error> {
+    pub fn json_value(&self) -> Result<serde_json::Value, RequestError> {
         self.json::<serde_json::Value>()
     }
 
+    /// Gets the local IP address the request was sent from.
+    ///
+    /// Returns `None` if cURL doesn't report one, e.g. for `file://` URLs.
+    pub fn local_ip(&self) -> Option<IpAddr> {
+        self.local_ip
+    }
+
+    /// Gets the local port the request was sent from.
+    pub fn local_port(&self) -> Option<u16> {
+        self.local_port
+    }
+
+    /// Gets the IP address of the server the response was received from.
+    ///
+    /// When the request went through a proxy, this is the address of the proxy.
+    /// Returns `None` if cURL doesn't report one, e.g. for `file://` URLs.
+    pub fn primary_ip(&self) -> Option<IpAddr> {
+        self.primary_ip
+    }
+
+    /// Gets the port of the server the response was received from.
+    ///
+    /// See [`Response::primary_ip`](#method.primary_ip) for more information.
+    pub fn primary_port(&self) -> Option<u16> {
+        self.primary_port
+    }
+
+    /// Gets the reason phrase of the response status, e.g. `"Not Found"`.
+    ///
+    /// HTTP/2 responses do not carry a reason phrase, so this falls back to the
+    /// [canonical reason](struct.StatusCode.html#method.canonical_reason) of
+    /// the status code. Returns `None` if neither is available.
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref().or_else(|| self.status().canonical_reason())
+    }
+
+    /// Gets the method of the request that produced the response.
+    pub fn request_method(&self) -> &Method {
+        &self.request_method
+    }
+
+    /// Gets the URL the request that produced the response was sent to,
+    /// including the query parameters.
+    ///
+    /// Unlike [`Response::effective_url`](struct.Response.html#method.effective_url),
+    /// this is not changed by redirects.
+    pub fn request_url(&self) -> &Url {
+        &self.request_url
+    }
+
+    /// Checks whether the request was sent over a connection kept alive from a
+    /// previous request instead of a new one.
+    ///
+    /// Connections are only reused between requests sent through the same `Session`,
+    /// e.g. through a [`Client`](struct.Client.html).
+    pub fn reused_connection(&self) -> bool {
+        self.reused_connection
+    }
+
+    /// Parses how long the server asks to wait before the next request from
+    /// the `Retry-After` header, e.g. in a response with status 429 or 503.
+    ///
+    /// Both the amount of seconds and the HTTP date forms are supported. Dates
+    /// in the past result in a zero duration. Returns `None` if the header is
+    /// missing or invalid.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.header("Retry-After")?.trim();
+        match value.parse() {
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(_) => parse_http_date(value).ok().map(|date| {
+                date.duration_since(SystemTime::now()).unwrap_or_default()
+            })
+        }
+    }
+
+    /// Gets the average download speed of the transfer in bytes per second.
+    ///
+    /// This is cURL's `CURLINFO_SPEED_DOWNLOAD_T`, which is measured over the
+    /// whole transfer, including name resolution and connecting.
+    pub fn speed_download(&self) -> f64 {
+        self.speed_download
+    }
+
+    /// Gets the average upload speed of the transfer in bytes per second.
+    ///
+    /// See [`Response::speed_download`](#method.speed_download) for more information.
+    pub fn speed_upload(&self) -> f64 {
+        self.speed_upload
+    }
+
     /// Gets the response status code.
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from(self.status_code)
+    }
+
+    /// Gets the response status code as integer.
+    ///
+    /// See [`Response::status`](#method.status) for a typed version.
     pub fn status_code(&self) -> u16 {
         self.status_code
     }
+
+    /// Decodes the response body to text using the charset declared in the
+    /// `Content-Type` header.
+    ///
+    /// Bodies without a declared charset or with an unknown one are decoded as
+    /// UTF-8, replacing invalid sequences with `U+FFFD`.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self.charset() {
+            Some(charset) => self.text_with_charset(&charset),
+            None => String::from_utf8_lossy(&self.body)
+        }
+    }
+
+    /// Decodes the response body to text using the charset with the given label,
+    /// e.g. `"ISO-8859-1"`, ignoring the `Content-Type` header.
+    ///
+    /// Labels are resolved like browsers do, so `ISO-8859-1` is decoded as its
+    /// superset `Windows-1252`. Unknown labels fall back to UTF-8, replacing invalid sequences with `U+FFFD`.
+    pub fn text_with_charset(&self, label: &str) -> Cow<'_, str> {
+        match Charset::for_label(label.trim().as_bytes()) {
+            Some(charset) => charset.decode(&self.body).0,
+            None => String::from_utf8_lossy(&self.body)
+        }
+    }
+
+    /// Gets the time spent in the individual phases of the transfer.
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Attempts to get a single trailer value.
+    ///
+    /// Trailers are headers sent after a chunked body, e.g. checksums or the
+    /// status of a gRPC-Web call. They are not contained in
+    /// [`Response::headers`](struct.Response.html#method.headers). Names are
+    /// matched case-insensitively and the first of multiple trailers with the
+    /// same name is returned.
+    pub fn trailer(&self, name: &str) -> Option<&str> {
+        self.trailers().find(|&(key, _)| key.eq_ignore_ascii_case(name))
+                       .map(|(_, value)| value)
+    }
+
+    /// Gets all trailers as name-value-pairs.
+    ///
+    /// See [`Response::trailer`](struct.Response.html#method.trailer) for more information.
+    pub fn trailers<'a>(&'a self) -> impl ExactSizeIterator<Item = (&'a str, &'a str)> + 'a {
+        self.trailers.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Checks that the response either is JSON or has no declared content type.
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn ensure_json(&self) -> Result<(), RequestError> {
+        match self.content_type() {
+            None | Some(Mime(TopLevel::Application, SubLevel::Json, _)) => Ok(()),
+            Some(Mime(_, SubLevel::Ext(ref sub), _)) if sub.ends_with("+json") => Ok(()),
+            Some(mime) => Err(RequestError::Decode(format!("Expected a JSON response, got {}.", mime).into()))
+        }
+    }
 }
 
 impl AsRef<[u8]> for Response {
@@ -177,8 +592,24 @@ impl Debug for Response {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
         fmt.debug_struct(stringify!(Response))
             .field("body_str", &self.body_str())
+            .field("bytes_downloaded", &self.bytes_downloaded)
+            .field("bytes_uploaded", &self.bytes_uploaded)
+            .field("effective_url", self.effective_url())
             .field("headers", &self.headers)
+            .field("http_version", &self.http_version)
+            .field("local_ip", &self.local_ip)
+            .field("local_port", &self.local_port)
+            .field("primary_ip", &self.primary_ip)
+            .field("primary_port", &self.primary_port)
+            .field("reason", &self.reason)
+            .field("request_method", &self.request_method)
+            .field("request_url", &self.request_url)
+            .field("reused_connection", &self.reused_connection)
+            .field("speed_download", &self.speed_download)
+            .field("speed_upload", &self.speed_upload)
             .field("status_code", &self.status_code)
+            .field("timing", &self.timing)
+            .field("trailers", &self.trailers)
             .finish()
     }
 }
@@ -195,4 +626,658 @@ impl From<Response> for Vec<u8> {
     fn from(response: Response) -> Self {
         response.body
     }
-}
\ No newline at end of file
+}
+/// Represents the status line and headers of an HTTP response whose body
+/// is still being received.
+///
+/// See [`Request::send_streaming`](struct.Request.html#method.send_streaming)
+/// for more information.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResponseHead {
+    headers: Vec<(String, String)>,
+    http_version: Option<HttpVersion>,
+    reason: Option<String>,
+    status_code: u16
+}
+
+impl ResponseHead {
+    /// Parses the raw header lines cURL reported for a transfer.
+    pub(crate) fn from_lines<S: AsRef<str>>(lines: &[S]) -> ResponseHead {
+        ResponseHead::with_trailers(lines).0
+    }
+
+    /// Parses the raw header lines cURL reported for a transfer into the head
+    /// and the trailers of the response.
+    ///
+    /// The header block of each response is terminated by an empty line, so
+    /// all headers after it are trailers sent after the body.
+    fn with_trailers<S: AsRef<str>>(lines: &[S]) -> (ResponseHead, Vec<(String, String)>) {
+        let mut head = ResponseHead {
+            headers: Vec::new(),
+            http_version: None,
+            reason: None,
+            status_code: 0
+        };
+        let mut trailers = Vec::new();
+        let mut in_trailers = false;
+        for line in lines {
+            let line = line.as_ref();
+
+            // Every response (e.g. a redirect) starts with a status line,
+            // only the headers of the final one are kept
+            if line.starts_with("HTTP/") {
+                let (version, phrase) = parse_status_line(line);
+                head.http_version = version;
+                head.reason = phrase;
+                head.status_code = line.split_whitespace().nth(1).and_then(|code| code.parse().ok()).unwrap_or(0);
+                head.headers.clear();
+                trailers.clear();
+                in_trailers = false;
+                continue;
+            }
+            if line.trim().is_empty() {
+                in_trailers = true;
+                continue;
+            }
+
+            if let Some(header) = parse_header_line(line) {
+                if in_trailers {
+                    trailers.push(header);
+                } else {
+                    head.headers.push(header);
+                }
+            }
+        }
+        (head, trailers)
+    }
+
+    /// Attempts to get a single header value.
+    ///
+    /// See [`Response::header`](struct.Response.html#method.header) for more information.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_all(name).into_iter().next()
+    }
+
+    /// Gets the values of all headers with the given name.
+    ///
+    /// See [`Response::header_all`](struct.Response.html#method.header_all) for more information.
+    pub fn header_all(&self, name: &str) -> Vec<&str> {
+        self.headers().filter(|&(key, _)| key.eq_ignore_ascii_case(name))
+                      .map(|(_, value)| value)
+                      .collect()
+    }
+
+    /// Gets all response headers as name-value-pairs.
+    pub fn headers<'a>(&'a self) -> impl ExactSizeIterator<Item = (&'a str, &'a str)> + 'a {
+        self.headers.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Gets the HTTP version of the response.
+    pub fn http_version(&self) -> Option<HttpVersion> {
+        self.http_version
+    }
+
+    /// Checks whether the returned status code represents a success
+    /// (HTTP status code 2xx) or not.
+    pub fn is_success(&self) -> bool {
+        self.status().is_success()
+    }
+
+    /// Gets the reason phrase of the response status, e.g. `"Not Found"`.
+    ///
+    /// See [`Response::reason`](struct.Response.html#method.reason) for more information.
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref().or_else(|| self.status().canonical_reason())
+    }
+
+    /// Gets the response status code.
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from(self.status_code)
+    }
+
+    /// Gets the response status code as integer.
+    pub fn status_code(&self) -> u16 {
+        self.status_code
+    }
+}
+
+const CURLINFO_OFF_T: curl_sys::CURLINFO = 0x600000;
+const CURLINFO_SIZE_UPLOAD_T: curl_sys::CURLINFO = CURLINFO_OFF_T + 7;
+const CURLINFO_SIZE_DOWNLOAD_T: curl_sys::CURLINFO = CURLINFO_OFF_T + 8;
+const CURLINFO_SPEED_DOWNLOAD_T: curl_sys::CURLINFO = CURLINFO_OFF_T + 9;
+const CURLINFO_SPEED_UPLOAD_T: curl_sys::CURLINFO = CURLINFO_OFF_T + 10;
+
+/// Reads an info of type `curl_off_t`, which the `curl` crate doesn't expose.
+fn get_off_t(easy: &Easy, info: curl_sys::CURLINFO) -> Result<u64, CurlError> {
+    let mut value: curl_sys::curl_off_t = 0;
+    match unsafe { curl_sys::curl_easy_getinfo(easy.raw(), info, &mut value) } {
+        curl_sys::CURLE_OK => Ok(value.max(0) as u64),
+        code => Err(CurlError::new(code))
+    }
+}
+
+/// Collects the cookies set by every response of a transfer, including the
+/// redirects cURL followed, each together with the URL of the response that
+/// set it.
+///
+/// The URL of a redirect target is resolved from the `Location` header of the
+/// redirect, starting from the given URL of the first request. Malformed cookies
+/// are skipped.
+pub(crate) fn hop_cookies<S: AsRef<str>>(lines: &[S], url: &Url) -> Vec<(Url, Cookie)> {
+    let mut cookies = Vec::new();
+    let mut url = url.clone();
+    let mut redirect = false;
+    let mut location = None;
+    let mut in_trailers = false;
+    for line in lines {
+        let line = line.as_ref();
+        if line.starts_with("HTTP/") {
+            if let Some(next) = location.take() {
+                url = next;
+            }
+            let status_code: u16 = line.split_whitespace().nth(1).and_then(|code| code.parse().ok()).unwrap_or(0);
+            redirect = (300..400).contains(&status_code);
+            in_trailers = false;
+            continue;
+        }
+        if line.trim().is_empty() {
+            in_trailers = true;
+            continue;
+        }
+        if in_trailers {
+            continue;
+        }
+
+        match parse_header_line(line) {
+            Some((ref name, ref value)) if name.eq_ignore_ascii_case("Set-Cookie") => {
+                if let Some(cookie) = Cookie::parse(value) {
+                    cookies.push((url.clone(), cookie));
+                }
+            },
+            Some((ref name, ref value)) if redirect && name.eq_ignore_ascii_case("Location") => {
+                location = url.join(value).ok();
+            },
+            _ => {}
+        }
+    }
+    cookies
+}
+
+/// Splits a header line like `Content-Type: text/plain` into name and value.
+fn parse_header_line(line: &str) -> Option<(String, String)> {
+    let splitted: Vec<_> = line.splitn(2, ": ")
+                               .map(|part| part.trim())
+                               .filter(|part| !part.is_empty())
+                               .collect();
+    if splitted.len() == 2 {
+        Some((splitted[0].to_owned(), splitted[1].to_owned()))
+    } else {
+        None
+    }
+}
+
+/// Parses the HTTP version and the reason phrase from a status line like
+/// `HTTP/1.1 404 Not Found`.
+fn parse_status_line(line: &str) -> (Option<HttpVersion>, Option<String>) {
+    let mut parts = line.trim().splitn(3, ' ');
+    let version = match parts.next() {
+        Some("HTTP/1.0") => Some(HttpVersion::Http10),
+        Some("HTTP/1.1") => Some(HttpVersion::Http11),
+        Some("HTTP/2") | Some("HTTP/2.0") => Some(HttpVersion::Http2),
+        _ => None
+    };
+    let reason = parts.nth(1)
+        .map(|reason| reason.trim())
+        .filter(|reason| !reason.is_empty())
+        .map(|reason| reason.to_owned());
+    (version, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::str::get;
+    use tests::serve;
+    use tokio_core::reactor::Core;
+
+    #[test]
+    fn allowed_methods() {
+        use ::Method;
+        use ::str::options;
+
+        let url = serve(b"HTTP/1.1 204 No Content\r\n\
+                          Allow: GET, HEAD,options\r\n\
+                          Allow: PROPFIND\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(options(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.allowed_methods(), vec![
+            Method::Get,
+            Method::Head,
+            Method::Options,
+            Method::Custom("PROPFIND".to_owned())
+        ]);
+
+        let url = serve(b"HTTP/1.1 204 No Content\r\n\r\n");
+        let result = evloop.run(options(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert!(result.allowed_methods().is_empty());
+    }
+
+    #[test]
+    fn binary_body() {
+        const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: image/png\r\n\
+                          Content-Length: 10\r\n\r\n\
+                          \x89PNG\r\n\x1a\n\xff\x00");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        assert_eq!(result.body_str(), None);
+        assert!(result.body().starts_with(PNG_MAGIC));
+        assert_eq!(result.into_body(), b"\x89PNG\r\n\x1a\n\xff\x00");
+    }
+
+    #[test]
+    fn chunked_body() {
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Transfer-Encoding: chunked\r\n\r\n\
+                          5;name=value\r\nHello\r\n\
+                          1 \r\n,\r\n\
+                          A;a=1;b=\"quoted\"\r\n chunked!\n\r\n\
+                          0;last\r\n\
+                          Checksum: abc\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        assert_eq!(result.body(), b"Hello, chunked!\n");
+        assert_eq!(result.trailer("Checksum"), Some("abc"));
+        assert_eq!(result.bytes_downloaded(), 16);
+    }
+
+    #[test]
+    fn content_length() {
+        use ::str::head;
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(head(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.content_length(), Some(1000));
+        assert!(result.body().is_empty());
+
+        let url = serve(b"HTTP/1.1 204 No Content\r\n\r\n");
+        let result = evloop.run(head(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.content_length(), None);
+    }
+
+    #[test]
+    fn content_type() {
+        use mime::{Mime, SubLevel, TopLevel};
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: 0\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        let Mime(top, sub, _) = result.content_type().unwrap();
+        assert_eq!((top, sub), (TopLevel::Application, SubLevel::Json));
+        assert_eq!(result.charset(), Some("utf-8".to_owned()));
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.content_type(), None);
+        assert_eq!(result.charset(), None);
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Type: not a mime\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.content_type(), None);
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn json_content_type() {
+        use ::RequestError;
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Length: 2\r\n\
+                          Content-Type: text/html\r\n\r\n{}");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        match result.json_value() {
+            Err(RequestError::Decode(_)) => {},
+            res => panic!("Expected a decode error, got {:?}.", res)
+        }
+    }
+
+    #[test]
+    fn cookies() {
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Length: 0\r\n\
+                          Set-Cookie: first=1; Path=/account; HttpOnly\r\n\
+                          set-cookie: second=2; domain=.example.com; SECURE\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        let cookies = result.cookies();
+
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name, "first");
+        assert_eq!(cookies[0].value, "1");
+        assert_eq!(cookies[0].path, Some("/account".to_owned()));
+        assert!(cookies[0].http_only && !cookies[0].secure);
+        assert_eq!(cookies[1].name, "second");
+        assert_eq!(cookies[1].domain, Some("example.com".to_owned()));
+        assert!(cookies[1].secure && !cookies[1].http_only);
+    }
+
+    #[test]
+    fn effective_url_fallback() {
+        use super::Response;
+        use curl::easy::Easy;
+        use url::Url;
+        use Method;
+
+        // A handle that never performed a transfer has no effective URL
+        let response = Response::new(Easy::new(), Vec::new(), Vec::new());
+        assert_eq!(response.effective_url().as_str(), "about:blank");
+
+        let url = Url::parse("http://example.com/path").unwrap();
+        let response = response.with_request(Method::Get, url.clone());
+        assert_eq!(response.effective_url(), &url);
+    }
+
+    #[test]
+    fn error_for_status() {
+        use ::RequestError;
+
+        let url = serve(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        match result.error_for_status() {
+            Err(RequestError::Status(404, ref err_url)) if *err_url == url => {},
+            res => panic!("Expected a status error, got {:?}", res)
+        }
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert!(result.error_for_status().is_ok());
+    }
+
+    #[test]
+    fn headers() {
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          content-length: 0\r\n\
+                          X-Custom-Header: value\r\n\
+                          Set-Cookie: first=1\r\n\
+                          set-cookie: second=2\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        assert_eq!(result.header("Content-Length"), Some("0"));
+        assert_eq!(result.header("x-custom-header"), Some("value"));
+        assert_eq!(result.header("Set-Cookie"), Some("first=1"));
+        assert_eq!(result.header_all("SET-COOKIE"), vec!["first=1", "second=2"]);
+        assert_eq!(result.header("Missing"), None);
+        assert_eq!(result.headers().len(), 4);
+        assert!(result.headers().any(|header| header == ("X-Custom-Header", "value")));
+    }
+
+    #[test]
+    fn hop_cookies() {
+        use super::hop_cookies;
+        use url::Url;
+
+        let url = Url::parse("http://example.com/login").unwrap();
+        let cookies = hop_cookies(&[
+            "HTTP/1.1 302 Found\r\n",
+            "Location: https://accounts.example.com/verify\r\n",
+            "Set-Cookie: first=1\r\n",
+            "\r\n",
+            "HTTP/1.1 303 See Other\r\n",
+            "Set-Cookie: second=2\r\n",
+            "Location: /done\r\n",
+            "\r\n",
+            "HTTP/1.1 200 OK\r\n",
+            "Set-Cookie: third=3\r\n",
+            "\r\n",
+            "Set-Cookie: trailer=4\r\n"
+        ], &url);
+
+        let cookies: Vec<_> = cookies.iter().map(|(url, cookie)| (url.as_str(), cookie.name.as_str())).collect();
+        assert_eq!(cookies, vec![
+            ("http://example.com/login", "first"),
+            ("https://accounts.example.com/verify", "second"),
+            ("https://accounts.example.com/done", "third")
+        ]);
+    }
+
+    #[test]
+    fn http_version() {
+        use ::HttpVersion;
+
+        let url = serve(b"HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.http_version(), Some(HttpVersion::Http10));
+
+        let url = serve(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.http_version(), Some(HttpVersion::Http11));
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn json() {
+        use std::collections::BTreeMap;
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Length: 15\r\n\
+                          Content-Type: application/json\r\n\r\n{\"a\":10,\"b\":15}");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        let data: BTreeMap<String, i32> = result.json().expect("Failed to parse response.");
+        assert_eq!(data.get("a"), Some(&10));
+        assert_eq!(data.get("b"), Some(&15));
+        assert!(result.json::<Vec<String>>().is_err());
+    }
+
+    #[test]
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    fn json_or_error() {
+        use ::{ApiError, StatusCode};
+        use std::collections::BTreeMap;
+
+        let url = serve(b"HTTP/1.1 422 Unprocessable Entity\r\n\
+                          Content-Length: 25\r\n\
+                          Content-Type: application/json\r\n\r\n{\"error\":\"invalid email\"}");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        match result.json_or_error::<Vec<String>, BTreeMap<String, String>>() {
+            Err(ApiError::Status(StatusCode::UNPROCESSABLE_ENTITY, ref body)) => {
+                assert_eq!(body.get("error").map(|e| e.as_str()), Some("invalid email"));
+            },
+            res => panic!("Expected an API error, got {:?}.", res)
+        }
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Length: 7\r\n\
+                          Content-Type: application/json\r\n\r\n[\"a\",1]");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        match result.json_or_error::<Vec<String>, BTreeMap<String, String>>() {
+            Err(ApiError::Request(::RequestError::Decode(_))) => {},
+            res => panic!("Expected a decode error, got {:?}.", res)
+        }
+
+        let url = serve(b"HTTP/1.1 502 Bad Gateway\r\n\
+                          Content-Length: 20\r\n\
+                          Content-Type: text/html\r\n\r\n<h1>Bad Gateway</h1>");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        match result.json_or_error::<Vec<String>, BTreeMap<String, String>>() {
+            Err(ApiError::StatusUndecodable(StatusCode::BAD_GATEWAY, ::RequestError::Decode(_))) => {},
+            res => panic!("Expected an undecodable API error, got {:?}.", res)
+        }
+    }
+
+    #[test]
+    fn reason() {
+        use super::parse_status_line;
+        use ::HttpVersion;
+
+        let url = serve(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.reason(), Some("Not Found"));
+
+        let url = serve(b"HTTP/1.1 200 Everything Fine\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.reason(), Some("Everything Fine"));
+
+        let url = serve(b"HTTP/1.1 299\r\nContent-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.reason(), None);
+
+        assert_eq!(parse_status_line("HTTP/2 200 \r\n"), (Some(HttpVersion::Http2), None));
+        assert_eq!(parse_status_line("HTTP/1.1 404 Not Found\r\n"), (Some(HttpVersion::Http11), Some("Not Found".to_owned())));
+    }
+
+    #[test]
+    fn request() {
+        use ::Method;
+        use ::str::post;
+        use tests::serve_sequence;
+
+        let url = serve_sequence(&[
+            b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ]);
+        let mut evloop = Core::new().unwrap();
+        let request = post(url.as_str())
+            .param("page", "1")
+            .body("data")
+            .send(evloop.handle());
+        let result = evloop.run(request).expect("HTTP Request failed!");
+
+        assert_eq!(result.request_method(), &Method::Post);
+        assert_eq!(result.request_url().as_str(), format!("{}?page=1", url));
+        assert_eq!(result.effective_url().path(), "/next");
+    }
+
+    #[test]
+    fn response_head() {
+        use super::ResponseHead;
+        use ::HttpVersion;
+
+        let head = ResponseHead::from_lines(&[
+            "HTTP/1.1 302 Found",
+            "Location: /get",
+            "HTTP/2 200",
+            "content-type: application/json",
+            "x-multi: a",
+            "x-multi: b"
+        ]);
+        assert_eq!(head.status_code(), 200);
+        assert_eq!(head.http_version(), Some(HttpVersion::Http2));
+        assert_eq!(head.reason(), Some("OK"));
+        assert_eq!(head.header("Location"), None);
+        assert_eq!(head.header("Content-Type"), Some("application/json"));
+        assert_eq!(head.header_all("X-Multi"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn response_head_trailers() {
+        use super::ResponseHead;
+
+        let (head, trailers) = ResponseHead::with_trailers(&[
+            "HTTP/1.1 100 Continue",
+            "",
+            "HTTP/1.1 200 OK",
+            "Transfer-Encoding: chunked",
+            "",
+            "Checksum: abc",
+            ""
+        ]);
+        assert_eq!(head.status_code(), 200);
+        assert_eq!(head.header("Transfer-Encoding"), Some("chunked"));
+        assert_eq!(head.header("Checksum"), None);
+        assert_eq!(trailers, vec![("Checksum".to_owned(), "abc".to_owned())]);
+    }
+
+    #[test]
+    fn retry_after() {
+        use httpdate::fmt_http_date;
+        use std::time::{Duration, SystemTime};
+
+        let mut evloop = Core::new().unwrap();
+        let url = serve(b"HTTP/1.1 429 Too Many Requests\r\n\
+                          Retry-After: 120\r\n\
+                          Content-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.retry_after(), Some(Duration::from_secs(120)));
+
+        let date = fmt_http_date(SystemTime::now() + Duration::from_secs(300));
+        let response = format!("HTTP/1.1 503 Service Unavailable\r\nRetry-After: {}\r\nContent-Length: 0\r\n\r\n", date);
+        let url = serve(Box::leak(response.into_bytes().into_boxed_slice()));
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        let delay = result.retry_after().expect("Missing Retry-After.");
+        assert!(delay > Duration::from_secs(290) && delay <= Duration::from_secs(300));
+
+        let url = serve(b"HTTP/1.1 503 Service Unavailable\r\n\
+                          Retry-After: Wed, 21 Oct 2015 07:28:00 GMT\r\n\
+                          Content-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.retry_after(), Some(Duration::from_secs(0)));
+
+        let url = serve(b"HTTP/1.1 503 Service Unavailable\r\n\
+                          Retry-After: soon\r\n\
+                          Content-Length: 0\r\n\r\n");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.retry_after(), None);
+    }
+
+    #[test]
+    fn text() {
+        let mut evloop = Core::new().unwrap();
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: text/plain; charset=ISO-8859-1\r\n\
+                          Content-Length: 10\r\n\r\n\
+                          Caf\xe9 cr\xe8me");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.body_str(), None);
+        assert_eq!(result.text(), "Café crème");
+        assert_eq!(result.text_with_charset("utf-8"), "Caf\u{fffd} cr\u{fffd}me");
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: text/plain; charset=\"windows-1252\"\r\n\
+                          Content-Length: 5\r\n\r\n\
+                          \x80 \x93\xe4\x94");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.text(), "€ “ä”");
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Content-Length: 7\r\n\r\n\
+                          Gr\xc3\xbc\xc3\x9fe");
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+        assert_eq!(result.text(), "Grüße");
+        assert_eq!(result.text_with_charset("latin1"), "GrÃ¼ÃŸe");
+    }
+
+    #[test]
+    fn trailers() {
+        let url = serve(b"HTTP/1.1 200 OK\r\n\
+                          Transfer-Encoding: chunked\r\n\
+                          Trailer: Grpc-Status, Grpc-Message\r\n\r\n\
+                          2\r\nOK\r\n\
+                          0\r\n\
+                          grpc-status: 0\r\n\
+                          Grpc-Message: done\r\n\r\n");
+        let mut evloop = Core::new().unwrap();
+        let result = evloop.run(get(url.as_str()).send(evloop.handle())).expect("HTTP Request failed!");
+
+        assert_eq!(result.body(), b"OK");
+        assert_eq!(result.trailer("Grpc-Status"), Some("0"));
+        assert_eq!(result.trailer("grpc-message"), Some("done"));
+        assert_eq!(result.trailer("Missing"), None);
+        assert_eq!(result.trailers().len(), 2);
+        assert_eq!(result.header("Grpc-Status"), None);
+        assert_eq!(result.header("Trailer"), Some("Grpc-Status, Grpc-Message"));
+    }
+}
@@ -0,0 +1,160 @@
+//! The module that contains the retry configuration.
+
+use std::time::Duration;
+
+use error::RequestError;
+use response::Response;
+
+/// The longest delay requested through `Retry-After` that is waited for
+/// before a retry.
+///
+/// See [`RetryPolicy::respect_retry_after`](struct.RetryPolicy.html#method.respect_retry_after)
+/// for more information.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Describes when and how often a failed request is re-issued.
+///
+/// The delay before a retry doubles with every attempt, starting at the
+/// base delay, unless the server asks for another one through `Retry-After`
+/// and the policy [respects it](#method.respect_retry_after). By default,
+/// connection errors and the status codes 500, 502, 503 and 504 trigger a retry. See [`Request::retry`](struct.Request.html#method.retry)
+/// on how to use it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    base_delay: Duration,
+    connection_errors: bool,
+    deadline: Option<Duration>,
+    max_attempts: u32,
+    retry_after: bool,
+    statuses: Vec<u16>
+}
+
+impl RetryPolicy {
+    /// Creates a new `RetryPolicy` sending the request at most `max_attempts`
+    /// times in total, waiting `base_delay` before the first retry.
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        RetryPolicy {
+            base_delay,
+            connection_errors: true,
+            deadline: None,
+            max_attempts,
+            retry_after: false,
+            statuses: vec![500, 502, 503, 504]
+        }
+    }
+
+    /// Limits the total time of all attempts including the delays in between.
+    ///
+    /// No retry is started if it would begin after the deadline, even if
+    /// attempts remain. The result of the last attempt is returned then. An
+    /// attempt in progress is not aborted when the deadline passes, which
+    /// [`Request::timeout`](struct.Request.html#method.timeout) limits instead.
+    pub fn overall_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the option whether to wait as long as the server asks for through
+    /// the `Retry-After` header of a response instead of the own backoff.
+    ///
+    /// Responses without a valid header fall back to the backoff. A response
+    /// asking for a delay longer than [`MAX_RETRY_AFTER`](constant.MAX_RETRY_AFTER.html)
+    /// is returned instead of being retried. Defaults to `false`. See [`Response::retry_after`](struct.Response.html#method.retry_after)
+    /// for the supported formats, and add 429 to the
+    /// [retried statuses](#method.retry_statuses) for rate limited APIs.
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.retry_after = respect;
+        self
+    }
+
+    /// Sets the option whether to retry requests that failed because the
+    /// connection could not be established or broke down.
+    ///
+    /// Defaults to `true`.
+    pub fn retry_connection_errors(mut self, retry: bool) -> Self {
+        self.connection_errors = retry;
+        self
+    }
+
+    /// Sets the response status codes that trigger a retry.
+    ///
+    /// This replaces the default status codes.
+    pub fn retry_statuses(mut self, statuses: &[u16]) -> Self {
+        self.statuses = statuses.to_owned();
+        self
+    }
+
+    /// Gets the delay before the retry following the given attempt.
+    ///
+    /// Returns `None` if the server asked to wait longer than
+    /// [`MAX_RETRY_AFTER`](constant.MAX_RETRY_AFTER.html), in which case no
+    /// retry should follow.
+    pub(crate) fn delay(&self, attempt: u32, result: &Result<Response, RequestError>) -> Option<Duration> {
+        match *result {
+            Ok(ref response) if self.retry_after => match response.retry_after() {
+                Some(delay) if delay > MAX_RETRY_AFTER => None,
+                Some(delay) => Some(delay),
+                None => Some(self.backoff(attempt))
+            },
+            _ => Some(self.backoff(attempt))
+        }
+    }
+
+    /// Gets the exponential backoff after the given attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .checked_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .unwrap_or(Duration::MAX)
+    }
+
+    /// Checks whether a retry after the given delay would start before the
+    /// deadline, given the time elapsed since the first attempt started.
+    pub(crate) fn within_deadline(&self, elapsed: Duration, delay: Duration) -> bool {
+        self.deadline.is_none_or(|deadline| elapsed.saturating_add(delay) < deadline)
+    }
+
+    /// Checks whether the given attempt should be followed by another one.
+    pub(crate) fn should_retry(&self, attempt: u32, result: &Result<Response, RequestError>) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        match *result {
+            Ok(ref response) => self.statuses.contains(&response.status_code()),
+            Err(RequestError::Curl(ref err)) if self.connection_errors => {
+                err.is_couldnt_connect() || err.is_got_nothing() || err.is_recv_error() || err.is_send_error()
+            },
+            Err(_) => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn delay() {
+        use error::RequestError;
+
+        let policy = RetryPolicy::new(5, Duration::from_millis(100)).respect_retry_after(true);
+        let result = Err(RequestError::Timeout);
+
+        assert_eq!(policy.delay(1, &result), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2, &result), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay(4, &result), Some(Duration::from_millis(800)));
+        assert!(policy.delay(100, &result) > Some(Duration::from_secs(60 * 60 * 24 * 365)));
+    }
+
+    #[test]
+    fn within_deadline() {
+        let policy = RetryPolicy::new(5, Duration::from_millis(100));
+        assert!(policy.within_deadline(Duration::MAX, Duration::from_secs(1)));
+
+        let policy = policy.overall_deadline(Duration::from_secs(1));
+        assert!(policy.within_deadline(Duration::from_millis(500), Duration::from_millis(400)));
+        assert!(!policy.within_deadline(Duration::from_millis(500), Duration::from_millis(500)));
+        assert!(!policy.within_deadline(Duration::from_secs(2), Duration::from_secs(0)));
+        assert!(!policy.within_deadline(Duration::MAX, Duration::MAX));
+    }
+}
@@ -0,0 +1,232 @@
+//! The module that contains the types for streaming response bodies.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::io::{self, Write};
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+use std::marker::PhantomData;
+#[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+use std::str;
+
+use error::RequestError;
+use futures::{Async, Future, Poll, Stream};
+use futures::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use response::ResponseHead;
+
+#[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+use rustc_serialize;
+
+#[cfg(feature = "serde-serialization")]
+use serde;
+#[cfg(feature = "serde-serialization")]
+use serde_json;
+
+/// The future returned when sending a [`Request`](struct.Request.html) with
+/// a streaming response body.
+///
+/// It resolves as soon as the head of the response has been received.
+pub type StreamingFuture = Box<dyn Future<Item = (ResponseHead, BodyStream), Error = RequestError>>;
+
+/// A message from the transfer to the consumer of the body.
+pub(crate) type Chunk = Result<Vec<u8>, RequestError>;
+
+/// The body of a response as a stream of chunks, in the sizes cURL
+/// delivers them in.
+///
+/// The stream ends when the transfer has completed and fails with the
+/// reason if the transfer failed midway. Dropping the stream aborts the
+/// transfer. See [`Request::send_streaming`](struct.Request.html#method.send_streaming)
+/// for more information.
+pub struct BodyStream {
+    rx: UnboundedReceiver<Chunk>
+}
+
+impl BodyStream {
+    pub(crate) fn new(rx: UnboundedReceiver<Chunk>) -> BodyStream {
+        BodyStream { rx }
+    }
+
+    /// Turns the stream into a stream of objects decoded from
+    /// newline-delimited JSON, as sent with `Content-Type: application/x-ndjson`.
+    ///
+    /// See [`JsonLines`](struct.JsonLines.html) for more information.
+    #[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+    pub fn json_lines<T>(self) -> JsonLines<T> {
+        JsonLines {
+            body: self,
+            buffer: Vec::new(),
+            done: false,
+            marker: PhantomData
+        }
+    }
+}
+
+impl Debug for BodyStream {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.debug_struct(stringify!(BodyStream)).finish()
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = Vec<u8>;
+    type Error = RequestError;
+
+    fn poll(&mut self) -> Poll<Option<Vec<u8>>, RequestError> {
+        match self.rx.poll() {
+            Ok(Async::Ready(Some(Ok(chunk)))) => Ok(Async::Ready(Some(chunk))),
+            Ok(Async::Ready(Some(Err(err)))) => Err(err),
+            Ok(Async::Ready(None)) | Err(_) => Ok(Async::Ready(None)),
+            Ok(Async::NotReady) => Ok(Async::NotReady)
+        }
+    }
+}
+
+/// A stream of objects decoded from the lines of a [`BodyStream`](struct.BodyStream.html).
+///
+/// Each line of the body is decoded from JSON as soon as it is complete, no
+/// matter how the lines are split across chunks. Blank lines are skipped and
+/// a trailing line without a line break is decoded once the body has ended.
+/// A line that cannot be decoded yields [`RequestError::Decode`](enum.RequestError.html#variant.Decode),
+/// after which the stream can be polled for the following lines.
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+pub struct JsonLines<T> {
+    body: BodyStream,
+    buffer: Vec<u8>,
+    done: bool,
+    marker: PhantomData<fn() -> T>
+}
+
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+impl<T> JsonLines<T> {
+    /// Takes the next non-blank line out of the buffer, including an
+    /// unterminated one once the body has ended.
+    fn next_line(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let line = match self.buffer.iter().position(|&b| b == b'\n') {
+                Some(pos) => {
+                    let mut line: Vec<u8> = self.buffer.drain(..pos + 1).collect();
+                    line.pop();
+                    line
+                },
+                None if self.done && !self.buffer.is_empty() => self.buffer.split_off(0),
+                None => return None
+            };
+            if line.iter().any(|b| !b.is_ascii_whitespace()) {
+                return Some(line);
+            }
+        }
+    }
+
+    fn poll_line(&mut self) -> Poll<Option<Vec<u8>>, RequestError> {
+        loop {
+            if let Some(line) = self.next_line() {
+                return Ok(Async::Ready(Some(line)));
+            }
+            if self.done {
+                return Ok(Async::Ready(None));
+            }
+            match self.body.poll()? {
+                Async::Ready(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Async::Ready(None) => self.done = true,
+                Async::NotReady => return Ok(Async::NotReady)
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "rustc-serialization", feature = "serde-serialization"))]
+impl<T> Debug for JsonLines<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.debug_struct(stringify!(JsonLines))
+            .field("buffered", &self.buffer.len())
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(all(feature = "rustc-serialization", not(feature = "serde-serialization")))]
+impl<T: rustc_serialize::Decodable> Stream for JsonLines<T> {
+    type Item = T;
+    type Error = RequestError;
+
+    fn poll(&mut self) -> Poll<Option<T>, RequestError> {
+        match self.poll_line()? {
+            Async::Ready(Some(line)) => {
+                let string = str::from_utf8(&line).map_err(|err| RequestError::Decode(Box::new(err)))?;
+                rustc_serialize::json::decode(string)
+                    .map(|item| Async::Ready(Some(item)))
+                    .map_err(|err| RequestError::Decode(Box::new(err)))
+            },
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::NotReady => Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(feature = "serde-serialization")]
+impl<T: serde::Deserialize> Stream for JsonLines<T> {
+    type Item = T;
+    type Error = RequestError;
+
+    fn poll(&mut self) -> Poll<Option<T>, RequestError> {
+        match self.poll_line()? {
+            Async::Ready(Some(line)) => serde_json::from_slice(&line)
+                .map(|item| Async::Ready(Some(item)))
+                .map_err(|err| RequestError::Decode(Box::new(err))),
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::NotReady => Ok(Async::NotReady)
+        }
+    }
+}
+
+/// Forwards everything written to it as chunks to a [`BodyStream`](struct.BodyStream.html).
+pub(crate) struct ChunkWriter {
+    tx: UnboundedSender<Chunk>
+}
+
+impl ChunkWriter {
+    pub(crate) fn new(tx: UnboundedSender<Chunk>) -> ChunkWriter {
+        ChunkWriter { tx }
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Failing here aborts the transfer once the stream has been dropped
+        self.tx.unbounded_send(Ok(buf.to_owned()))
+            .map(|_| buf.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The body stream was dropped."))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde-serialization"))]
+mod tests {
+    use super::BodyStream;
+
+    use error::RequestError;
+    use futures::Stream;
+    use futures::sync::mpsc::unbounded;
+    use serde_json::Value;
+
+    #[test]
+    fn json_lines() {
+        let (tx, rx) = unbounded();
+        for chunk in &[&b"{\"id\": 0}\n{\"i"[..], b"d\": 1}\r\n", b"\n", b"nope\n{\"id\": 2}"] {
+            tx.unbounded_send(Ok(chunk.to_vec())).unwrap();
+        }
+        drop(tx);
+
+        let mut lines = BodyStream::new(rx).json_lines::<Value>().wait();
+        assert_eq!(lines.next().unwrap().unwrap().find("id").and_then(|id| id.as_u64()), Some(0));
+        assert_eq!(lines.next().unwrap().unwrap().find("id").and_then(|id| id.as_u64()), Some(1));
+        match lines.next() {
+            Some(Err(RequestError::Decode(_))) => {},
+            res => panic!("Expected a decode error, got {:?}", res)
+        }
+        assert_eq!(lines.next().unwrap().unwrap().find("id").and_then(|id| id.as_u64()), Some(2));
+        assert!(lines.next().is_none());
+    }
+}
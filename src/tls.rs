@@ -0,0 +1,31 @@
+//! The module that contains the TLS client certificate types.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Represents the format of a TLS client certificate.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CertificateType {
+    /// DER-encoded certificate and key
+    Der,
+    /// PKCS#12 archive containing both the certificate and the key
+    P12,
+    /// PEM-encoded certificate and key
+    #[default]
+    Pem
+}
+
+impl AsRef<str> for CertificateType {
+    fn as_ref(&self) -> &str {
+        match *self {
+            CertificateType::Der => "DER",
+            CertificateType::P12 => "P12",
+            CertificateType::Pem => "PEM"
+        }
+    }
+}
+
+impl Display for CertificateType {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.write_str(self.as_ref())
+    }
+}